@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use crate::{
+    commands::find_pools::FindPoolsSource,
+    utils::{client::WsolMode, compute_budget::PriorityFeeStrategy, printer::OutputFormat},
+};
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -12,6 +16,34 @@ pub struct Cli {
     #[arg(short, long)]
     pub debug: bool,
 
+    /// Priority fee percentile, overrides the config
+    #[arg(long, value_enum)]
+    pub priority_fee_strategy: Option<PriorityFeeStrategy>,
+
+    /// Minimum compute unit price in micro-lamports, overrides the config
+    #[arg(long)]
+    pub min_priority_fee: Option<u64>,
+
+    /// Maximum compute unit price in micro-lamports, overrides the config
+    #[arg(long)]
+    pub max_priority_fee: Option<u64>,
+
+    /// Base URL of the Raydium API, overrides the config
+    #[arg(long)]
+    pub api_base_url: Option<String>,
+
+    /// Simulate write commands and report balance changes without sending
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Skip the confirmation prompt, enforcing the configured guardrails instead
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Format of the command result written to stdout
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -20,16 +52,31 @@ pub struct Cli {
 pub enum Command {
     /// Fetch pool data by pool id
     FetchPoolInfo {
-        /// Pool ID
-        #[arg(short, long)]
-        pool_id: String,
+        #[command(flatten)]
+        pools: PoolIdArgs,
     },
     /// Fetch pool keys by pool id
     FetchPoolKeys {
-        /// Pool ID
-        #[arg(short, long)]
-        pool_id: String,
+        #[command(flatten)]
+        pools: PoolIdArgs,
+        /// Check the Raydium API keys against the chain
+        #[arg(long)]
+        verify: bool,
     },
+    /// Find the AMM v4 pools trading two mints, in either order
+    FindPools {
+        /// First mint
+        #[arg(long)]
+        mint_a: String,
+        /// Second mint
+        #[arg(long)]
+        mint_b: String,
+        /// Look the pools up through the Raydium API or the AMM v4 program accounts
+        #[arg(long, value_enum, default_value_t = FindPoolsSource::Api)]
+        source: FindPoolsSource,
+    },
+    /// List the Raydium LP positions held by the wallet
+    Positions,
     /// Add liquidity to a pool
     AddLiquidity {
         /// Pool ID
@@ -44,6 +91,8 @@ pub enum Command {
         /// Slippage percentage
         #[arg(short, long)]
         slippage_percentage: f64,
+        #[command(flatten)]
+        wsol: WsolArgs,
     },
     /// Remove liquidity from a pool
     RemoveLiquidity {
@@ -56,11 +105,110 @@ pub enum Command {
         /// Slippage percentage
         #[arg(short, long)]
         slippage_percentage: f64,
-        // Base amount min
+        /// Base amount min, derived from the slippage if omitted
+        #[arg(short, long)]
+        base_amount_min: Option<f64>,
+        /// Quote amount min, derived from the slippage if omitted
+        #[arg(short, long)]
+        quote_amount_min: Option<f64>,
+        #[command(flatten)]
+        wsol: WsolArgs,
+    },
+    /// Add liquidity and remove it again in one transaction
+    RoundTrip {
+        /// Pool ID
+        #[arg(short, long)]
+        pool_id: String,
+        /// Mint of the side to deposit
+        #[arg(short, long)]
+        mint_pubkey: String,
+        /// Deposit amount of the given mint
+        #[arg(short, long)]
+        amount: f64,
+        /// Slippage percentage
+        #[arg(short, long)]
+        slippage_percentage: f64,
+        #[command(flatten)]
+        wsol: WsolArgs,
+    },
+    /// Swap through a pool
+    Swap {
+        /// Pool ID
+        #[arg(short, long)]
+        pool_id: String,
+        /// Mint of the token to sell
+        #[arg(short, long)]
+        mint_pubkey: String,
+        /// Exact amount to sell
+        #[arg(
+            long,
+            required_unless_present = "amount_out",
+            conflicts_with = "amount_out"
+        )]
+        amount_in: Option<f64>,
+        /// Exact amount to buy
+        #[arg(long)]
+        amount_out: Option<f64>,
+        /// Slippage percentage
+        #[arg(short, long)]
+        slippage_percentage: f64,
+        #[command(flatten)]
+        wsol: WsolArgs,
+    },
+    /// Swap part of a single-sided amount and add liquidity in one transaction
+    ZapIn {
+        /// Pool ID
+        #[arg(short, long)]
+        pool_id: String,
+        /// Mint of the side held
+        #[arg(short, long)]
+        mint_pubkey: String,
+        /// Amount of the given mint to deposit
+        #[arg(short, long)]
+        amount: f64,
+        /// Slippage percentage
+        #[arg(short, long)]
+        slippage_percentage: f64,
+        #[command(flatten)]
+        wsol: WsolArgs,
+    },
+    /// Remove liquidity and swap it into a single asset in one transaction
+    ZapOut {
+        /// Pool ID
+        #[arg(short, long)]
+        pool_id: String,
+        /// LP amount
+        #[arg(short, long)]
+        lp_amount: f64,
+        /// Mint to settle in, USDC or SOL
         #[arg(short, long)]
-        base_amount_min: f64,
-        // Quote amount min
+        mint_pubkey: String,
+        /// Slippage percentage
         #[arg(short, long)]
-        quote_amount_min: f64,
+        slippage_percentage: f64,
+        #[command(flatten)]
+        wsol: WsolArgs,
     },
 }
+
+#[derive(Args, Debug)]
+pub struct PoolIdArgs {
+    /// Pool ID, may be repeated
+    #[arg(short, long = "pool-id", required_unless_present = "pool_ids_file")]
+    pub pool_id: Vec<String>,
+
+    /// File listing pool IDs, one per line, `#` starting a comment
+    #[arg(long)]
+    pub pool_ids_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct WsolArgs {
+    /// How to wrap SOL, overrides the config
+    #[arg(long, value_enum)]
+    pub wsol_mode: Option<WsolMode>,
+
+    /// Keep the wrapped SOL in the WSOL account instead of unwrapping it
+    #[arg(long)]
+    pub keep_wsol: bool,
+}
@@ -1,329 +1,123 @@
 use crate::{
-    commands::{
-        fetch_pool_info::{fetch_pool_info, PoolInfo},
-        fetch_pool_keys::{fetch_pool_keys, PoolKeys},
-    },
-    config::Config,
+    commands::{fetch_pool_keys::PoolKeys, CommandResult},
     error::Error,
-    instructions::add_liquidity::{
-        make_add_liquidity_instruction, FixedSide, IxUserKeys, LiquidityAddInstructionParams,
-    },
+    instructions::add_liquidity::FixedSide,
+    pool_source::PoolSource,
+    raydium_v4::{AddQuote, RaydiumV4Client},
     utils::{
         amount::{amount_display_to_raw, amount_raw_to_display},
-        client::{Client, HandleTokenAccountParams, TokenSide},
-        compute_budget::{add_compute_budget, ComputeBudgetConfig},
+        client::WsolOptions,
         confirmation::get_confirmation,
+        guardrails::Trade,
         pubkey::pubkey_from_str,
     },
 };
 
-use rust_decimal::{prelude::FromPrimitive, Decimal};
-use solana_sdk::{
-    instruction::Instruction,
-    pubkey::Pubkey,
-    signature::{Keypair, Signature},
-};
-use std::ops::Sub;
-use std::str::FromStr;
+use rust_decimal::prelude::ToPrimitive;
+use solana_sdk::pubkey::Pubkey;
 use tracing::{debug, info};
 
-pub async fn execute(
-    config: &Config,
-    client: &Client,
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
     pool_id: &str,
     base_mint_pubkey: &str,
     base_amount: f64,
     slippage_percentage: f64,
-) -> Result<(), Error> {
-    let api_response = fetch_pool_info(config, pool_id).await?;
-    let pool_info = api_response.data.first().unwrap();
-    let pool_keys = fetch_pool_keys(config, pool_id).await?;
-    let pool_keys = pool_keys.data.first().unwrap();
-
-    let base_mint_pubkey = pubkey_from_str(base_mint_pubkey)?;
-    let signature = add_liquidity(
-        client,
-        pool_info,
-        pool_keys,
-        &base_mint_pubkey,
-        base_amount,
-        slippage_percentage,
-    )
-    .await?;
-
-    if let Some(signature) = signature {
-        info!("Transaction sent with signature: {}", signature);
-    } else {
-        info!("No transaction sent");
-    }
-
-    Ok(())
-}
-
-async fn add_liquidity(
-    client: &Client,
-    pool_info: &PoolInfo,
-    pool_keys: &PoolKeys,
-    input_mint_pubkey: &Pubkey,
-    input_amount: f64,
-    slippage_percentage: f64,
-) -> Result<Option<Signature>, Error> {
-    let mut instruction_options: Vec<Option<Instruction>> = vec![];
-    let mut additional_signers: Vec<Keypair> = vec![];
-
-    let compute_budget_ixs = add_compute_budget(&ComputeBudgetConfig {
-        micro_lamports: Some(1_000_000),
-        units: Some(1_000_000),
-    });
-    for ix in compute_budget_ixs {
-        instruction_options.push(Some(ix.instruction));
-    }
-
-    let mint_a =
-        Pubkey::from_str(&pool_keys.mintA.address).map_err(|e| Error::RpcClient(e.to_string()))?;
-    let mint_b =
-        Pubkey::from_str(&pool_keys.mintB.address).map_err(|e| Error::RpcClient(e.to_string()))?;
-    let mint_lp =
-        Pubkey::from_str(&pool_keys.mintLp.address).map_err(|e| Error::RpcClient(e.to_string()))?;
-
-    let (input_amount, other_amount, min_other_amount) = calculate_values_from_input(
-        client,
-        pool_info,
-        pool_keys,
-        input_mint_pubkey,
-        input_amount,
-        slippage_percentage,
-    )?;
-
-    let (fixed_side, base_amount, quote_amount) = if input_mint_pubkey == &mint_a {
-        (FixedSide::Base, input_amount, other_amount)
-    } else {
-        (FixedSide::Quote, other_amount, input_amount)
+    wsol: WsolOptions,
+    dry_run: bool,
+    yes: bool,
+) -> Result<CommandResult<AddQuote>, Error> {
+    let pool_keys = raydium.pool_keys(pool_id).await?;
+
+    let fixed_side = fixed_side_for_mint(&pool_keys, &pubkey_from_str(base_mint_pubkey)?)?;
+    let input_decimals = match fixed_side {
+        FixedSide::Base => pool_keys.mintA.decimals,
+        FixedSide::Quote => pool_keys.mintB.decimals,
     };
+    let input_amount_raw = amount_display_to_raw(base_amount, input_decimals)
+        .to_u64()
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid amount: {}", base_amount)))?;
+    debug!("Input Amount Raw: {}", input_amount_raw);
 
-    let token_a_info = client
-        .handle_token_account(HandleTokenAccountParams {
-            side: TokenSide::In,
-            amount: base_amount,
-            mint: mint_a,
-            token_account: None,
-            bypass_associated_check: false,
-            skip_close_account: false,
-            check_create_ata_owner: true,
-        })
-        .await?;
-    for ix in &token_a_info.start_instructions {
-        instruction_options.push(Some(ix.clone()));
-    }
-    for signer in token_a_info.additional_signers {
-        additional_signers.push(signer);
-    }
-
-    let token_b_info = client
-        .handle_token_account(HandleTokenAccountParams {
-            side: TokenSide::In,
-            amount: quote_amount,
-            mint: mint_b,
-            token_account: None,
-            bypass_associated_check: false,
-            skip_close_account: false,
-            check_create_ata_owner: true,
-        })
-        .await?;
-    for ix in &token_b_info.start_instructions {
-        instruction_options.push(Some(ix.clone()));
-    }
-    for signer in token_b_info.additional_signers {
-        additional_signers.push(signer);
-    }
-
-    let token_lp_info = client
-        .handle_token_account(HandleTokenAccountParams {
-            side: TokenSide::Out, // Because we're receiving LP tokens
-            amount: 0,            // Initial LP token amount is 0
-            mint: mint_lp,
-            token_account: None,
-            bypass_associated_check: false,
-            skip_close_account: false,
-            check_create_ata_owner: true,
-        })
+    let quote = raydium
+        .quote_add(
+            &pool_keys,
+            fixed_side,
+            input_amount_raw,
+            slippage_percentage,
+        )
         .await?;
-    for ix in &token_lp_info.start_instructions {
-        instruction_options.push(Some(ix.clone()));
-    }
-    for signer in token_lp_info.additional_signers {
-        additional_signers.push(signer);
+    let transaction = raydium.build_add(&pool_keys, &quote, wsol).await?;
+
+    // Check the user balances of the base and quote accounts
+    for account in transaction.watched.iter().take(2) {
+        match raydium
+            .client()
+            .get_token_account_balance_ui_amount(&account.address)
+            .await
+        {
+            Ok(balance) => {
+                info!("User balance {}: {}", account.label, balance);
+            }
+            Err(e) => {
+                info!("Failed getting user balance {}: {}", account.label, e);
+            }
+        };
     }
 
-    // Check the user balances of the input mint and the other mint
-    match client.get_token_account_balance_ui_amount(&token_a_info.token_account) {
-        Ok(balance) => {
-            info!("User balance mint a: {} {}", mint_a, balance);
-        }
-        Err(e) => {
-            info!("Failed getting user balance mint a: {}", e);
-        }
+    let other_decimals = match fixed_side {
+        FixedSide::Base => pool_keys.mintB.decimals,
+        FixedSide::Quote => pool_keys.mintA.decimals,
     };
-
-    match client.get_token_account_balance_ui_amount(&token_b_info.token_account) {
-        Ok(balance) => {
-            info!("User balance mint b: {} {}", mint_b, balance);
-        }
-        Err(e) => {
-            info!("Failed getting user balance mint b: {}", e);
-        }
-    };
-
-    let base_amount_display = amount_raw_to_display(base_amount, pool_info.mintA.decimals);
-    let quote_amount_display = amount_raw_to_display(quote_amount, pool_info.mintB.decimals);
-    let min_other_amount_display =
-        amount_raw_to_display(min_other_amount, pool_info.mintB.decimals);
-
     let confirmation_msg = format!(
-        "{} Amount: {}, {} Amount: {}, Min Other Amount: {}",
-        pool_info.mintA.symbol,
-        base_amount_display,
-        pool_info.mintB.symbol,
-        quote_amount_display,
-        min_other_amount_display
+        "{} Amount: {}, {} Amount: {}, Min Other Amount: {}, LP Amount: {}",
+        pool_keys.mintA.label(),
+        amount_raw_to_display(quote.deposit.base_amount, pool_keys.mintA.decimals),
+        pool_keys.mintB.label(),
+        amount_raw_to_display(quote.deposit.quote_amount, pool_keys.mintB.decimals),
+        amount_raw_to_display(quote.other_amount_min, other_decimals),
+        amount_raw_to_display(quote.deposit.lp_amount, pool_keys.mintLp.decimals),
     );
 
-    get_confirmation(&confirmation_msg);
-
-    let add_liquidity_ix = make_add_liquidity_instruction(LiquidityAddInstructionParams {
-        rpc_pool_keys: &pool_keys,
-        user_keys: &IxUserKeys {
-            base_token_account: token_a_info.token_account,
-            quote_token_account: token_b_info.token_account,
-            lp_token_account: token_lp_info.token_account,
-            owner: client.get_payer_pubkey(),
+    get_confirmation(
+        raydium.config(),
+        raydium.pool_source(),
+        &confirmation_msg,
+        &Trade {
+            pool_keys: &pool_keys,
+            slippage_percentage,
+            base_amount: quote.max_base_amount,
+            quote_amount: quote.max_quote_amount,
         },
-        base_amount_in: base_amount,
-        quote_amount_in: quote_amount,
-        fixed_side,
-        other_amount_min: min_other_amount,
-    })?;
-
-    instruction_options.push(Some(add_liquidity_ix));
-
-    for ix in &token_a_info.end_instructions {
-        instruction_options.push(Some(ix.clone()));
-    }
-    for ix in &token_b_info.end_instructions {
-        instruction_options.push(Some(ix.clone()));
-    }
-    for ix in &token_lp_info.end_instructions {
-        instruction_options.push(Some(ix.clone()));
-    }
-
-    let instructions = instruction_options
-        .into_iter()
-        .filter(|ix| ix.is_some())
-        .map(|ix| ix.unwrap())
-        .collect::<Vec<Instruction>>();
-
-    let mut signature = None;
+        yes,
+        dry_run,
+    )
+    .await?;
 
-    if !instructions.is_empty() {
-        signature = Some(client.send_transaction(&instructions, &additional_signers)?);
+    let transaction = raydium.execute(&transaction, dry_run).await?;
+    if let Some(signature) = &transaction.signature {
+        info!("Transaction sent with signature: {}", signature);
+    } else {
+        info!("Transaction simulated, nothing sent");
     }
 
-    Ok(signature)
+    Ok(CommandResult {
+        pool_id: pool_keys.id.clone(),
+        quote,
+        transaction,
+    })
 }
 
-fn calculate_values_from_input(
-    client: &Client,
-    pool_info: &PoolInfo,
-    pool_keys: &PoolKeys,
-    input_mint_pubkey: &Pubkey,
-    input_amount: f64,
-    slippage_percentage: f64,
-) -> Result<(u64, u64, u64), Error> {
-    let (input_mint, _) = if &input_mint_pubkey.to_string() == &pool_keys.mintA.address {
-        (&pool_keys.mintA, &pool_keys.mintB)
+/// Side of the pool `mint` is deposited on.
+pub(crate) fn fixed_side_for_mint(pool_keys: &PoolKeys, mint: &Pubkey) -> Result<FixedSide, Error> {
+    if mint.to_string() == pool_keys.mintA.address {
+        Ok(FixedSide::Base)
+    } else if mint.to_string() == pool_keys.mintB.address {
+        Ok(FixedSide::Quote)
     } else {
-        (&pool_keys.mintB, &pool_keys.mintA)
-    };
-
-    // Convert input amount to u128
-    let input_amount_raw = amount_display_to_raw(input_amount, input_mint.decimals)
-        .to_string()
-        .parse::<u128>()
-        .unwrap();
-    debug!("Input Amount Raw: {}", input_amount_raw);
-
-    // Fetch the reserves from the pool token accounts
-    let (input_reserve, other_reserve) =
-        if &input_mint_pubkey.to_string() == &pool_info.mintA.address {
-            (
-                client
-                    .get_token_account_balance_string(&pubkey_from_str(&pool_keys.vault.A)?)?
-                    .parse::<u128>()
-                    .unwrap(),
-                client
-                    .get_token_account_balance_string(&pubkey_from_str(&pool_keys.vault.B)?)?
-                    .parse::<u128>()
-                    .unwrap(),
-            )
-        } else {
-            (
-                client
-                    .get_token_account_balance_string(&pubkey_from_str(&pool_keys.vault.B)?)?
-                    .parse::<u128>()
-                    .unwrap(),
-                client
-                    .get_token_account_balance_string(&pubkey_from_str(&pool_keys.vault.A)?)?
-                    .parse::<u128>()
-                    .unwrap(),
-            )
-        };
-
-    debug!("Input Reserve: {}", input_reserve);
-    debug!("Other Reserve: {}", other_reserve);
-
-    let next_input_reserve = input_reserve + input_amount_raw;
-    debug!("Next Input Reserve: {}", next_input_reserve);
-
-    // Calculate constant product k = x * y
-    let k = input_reserve
-        .checked_mul(other_reserve)
-        .ok_or_else(|| Error::Math("Overflow in k calculation".to_string()))?;
-    debug!("Constant k: {}", k);
-
-    // Calculate new other reserve: k / next_input_reserve
-    let next_other_reserve = k
-        .checked_div(next_input_reserve)
-        .ok_or_else(|| Error::Math("Division error".to_string()))?;
-    debug!("Next Other Reserve: {}", next_other_reserve);
-
-    // Calculate other amount
-    let other_amount = other_reserve
-        .checked_sub(next_other_reserve)
-        .ok_or_else(|| Error::Math("Subtraction error".to_string()))?;
-    debug!("Other Amount: {}", other_amount);
-
-    let slippage_decimal = Decimal::from_f64(slippage_percentage).unwrap() / Decimal::from(100u64);
-    debug!("Slippage Decimal: {}", slippage_decimal);
-    let coefficient = Decimal::ONE
-        .sub(slippage_decimal)
-        .to_string()
-        .parse::<f64>()
-        .unwrap();
-
-    // Calculate minimum amounts using the same process
-    let min_other_amount = (other_amount as f64 * coefficient) as u128;
-    debug!("Min Other Amount: {}", min_other_amount);
-
-    // Convert back to u64 for return values
-    Ok((
-        input_amount_raw
-            .try_into()
-            .map_err(|_| Error::Math("Overflow converting to u64".to_string()))?,
-        other_amount
-            .try_into()
-            .map_err(|_| Error::Math("Overflow converting to u64".to_string()))?,
-        min_other_amount
-            .try_into()
-            .map_err(|_| Error::Math("Overflow converting to u64".to_string()))?,
-    ))
+        Err(Error::InvalidInput(format!(
+            "Mint {} is not in pool {}",
+            mint, pool_keys.id
+        )))
+    }
 }
@@ -1,13 +1,18 @@
-use crate::{config::Config, error::Error, utils::printer::pretty_print};
+use crate::{
+    error::Error,
+    pool_source::{all_found, PoolSource},
+    raydium_v4::RaydiumV4Client,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tracing::{debug, info};
+use std::collections::BTreeMap;
 
+/// Pools by LP mint, `None` for mints that aren't Raydium LP mints.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ApiResponse {
+pub struct LpApiResponse {
     pub id: String,
     pub success: bool,
-    pub data: Vec<PoolInfo>,
+    pub data: Vec<Option<PoolInfo>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,35 +29,55 @@ pub struct PoolInfo {
     pub feeRate: f64,
     pub openTime: String,
     pub tvl: f64,
+    #[serde(default)]
     pub day: PeriodStats,
+    #[serde(default)]
     pub week: PeriodStats,
+    #[serde(default)]
     pub month: PeriodStats,
+    #[serde(default)]
     pub pooltype: Vec<String>,
+    #[serde(default)]
     pub rewardDefaultInfos: Vec<Value>,
+    #[serde(default)]
     pub farmUpcomingCount: i32,
+    #[serde(default)]
     pub farmOngoingCount: i32,
+    #[serde(default)]
     pub farmFinishedCount: i32,
+    #[serde(default)]
     pub marketId: String,
     pub lpMint: LpMintInfo,
+    #[serde(default)]
     pub lpPrice: f64,
+    #[serde(default)]
     pub lpAmount: f64,
+    #[serde(default)]
     pub burnPercent: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenInfo {
+    #[serde(default)]
     pub chainId: i64,
     pub address: String,
+    #[serde(default)]
     pub programId: String,
+    #[serde(default)]
     pub logoURI: String,
+    #[serde(default)]
     pub symbol: String,
+    #[serde(default)]
     pub name: String,
     pub decimals: i32,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
     pub extensions: Value,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PeriodStats {
     pub volume: f64,
     pub volumeQuote: f64,
@@ -66,49 +91,27 @@ pub struct PeriodStats {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LpMintInfo {
+    #[serde(default)]
     pub chainId: i64,
     pub address: String,
+    #[serde(default)]
     pub programId: String,
+    #[serde(default)]
     pub logoURI: String,
+    #[serde(default)]
     pub symbol: String,
+    #[serde(default)]
     pub name: String,
     pub decimals: i32,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
     pub extensions: Value,
 }
 
-pub async fn execute(config: &Config, pool_id: &str) -> Result<(), Error> {
-    let pool = fetch_pool_info(config, pool_id).await?;
-    info!("{}", pretty_print(&pool));
-    Ok(())
-}
-
-pub async fn fetch_pool_info(config: &Config, pool_id: &str) -> Result<ApiResponse, Error> {
-    let url = format!("https://api-v3.raydium.io/pools/info/ids?ids={}", pool_id);
-
-    debug!("Requesting URL: {}", url);
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| Error::Api(e.to_string()))?;
-
-    if !response.status().is_success() {
-        return Err(Error::Api(format!("API error: {}", response.status())));
-    }
-
-    // Get raw response and log it in debug mode
-    let text = response
-        .text()
-        .await
-        .map_err(|e| Error::Api(e.to_string()))?;
-    debug!("Raw response: {}", text);
-
-    // Parse response
-    let pool: ApiResponse =
-        serde_json::from_str(&text).map_err(|e| Error::Api(format!("Parse error: {}", e)))?;
-
-    Ok(pool)
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
+    pool_ids: &[String],
+) -> Result<BTreeMap<String, PoolInfo>, Error> {
+    all_found(raydium.pool_source().pool_info_by_ids(pool_ids).await?)
 }
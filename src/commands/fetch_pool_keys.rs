@@ -1,14 +1,22 @@
-use crate::{config::Config, error::Error, utils::printer::pretty_print};
+use crate::{
+    error::Error,
+    pool_source::{all_found, ApiPoolSource, PoolSource},
+    raydium_v4::RaydiumV4Client,
+    state::{
+        amm_info::{AmmInfo, AMM_V4_PROGRAM_ID},
+        market::{market_keys_from_account, MarketKeys},
+    },
+    utils::{client::Client, pubkey::pubkey_from_str},
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tracing::{debug, info};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+use std::collections::BTreeMap;
+use tracing::{debug, info, warn};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ApiResponse {
-    pub id: String,
-    pub success: bool,
-    pub data: Vec<PoolKeys>,
-}
+/// Maximum number of accounts read per RPC call.
+const ACCOUNTS_PER_REQUEST: usize = 100;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PoolKeys {
@@ -16,7 +24,9 @@ pub struct PoolKeys {
     pub id: String,
     pub mintA: TokenInfo,
     pub mintB: TokenInfo,
+    #[serde(default)]
     pub lookupTableAccount: String,
+    #[serde(default)]
     pub openTime: String,
     pub vault: VaultInfo,
     pub authority: String,
@@ -35,14 +45,20 @@ pub struct PoolKeys {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenInfo {
+    #[serde(default)]
     pub chainId: i64,
     pub address: String,
     pub programId: String,
+    #[serde(default)]
     pub logoURI: String,
+    #[serde(default)]
     pub symbol: String,
+    #[serde(default)]
     pub name: String,
     pub decimals: i32,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
     pub extensions: Value,
 }
 
@@ -52,38 +68,308 @@ pub struct VaultInfo {
     pub B: String,
 }
 
-pub async fn execute(config: &Config, pool_id: &str) -> Result<(), Error> {
-    let pool = fetch_pool_keys(config, pool_id).await?;
-    info!("{}", pretty_print(&pool));
-    Ok(())
+impl TokenInfo {
+    /// Symbol when known, mint address otherwise.
+    pub fn label(&self) -> &str {
+        if self.symbol.is_empty() {
+            &self.address
+        } else {
+            &self.symbol
+        }
+    }
+
+    /// Token program owning the mint. Guessing it would let a Token-2022
+    /// mint through, so an empty one is refused.
+    pub fn program_id(&self) -> Result<Pubkey, Error> {
+        if self.programId.is_empty() {
+            return Err(Error::SchemaMismatch {
+                field: format!("{}.programId", self.address),
+            });
+        }
+
+        pubkey_from_str(&self.programId)
+    }
+}
+
+impl PoolKeys {
+    /// Overwrite the market accounts with the given keys.
+    pub fn set_market_keys(&mut self, market_keys: &MarketKeys) {
+        self.marketProgramId = market_keys.program_id.to_string();
+        self.marketId = market_keys.id.to_string();
+        self.marketAuthority = market_keys.authority.to_string();
+        self.marketBaseVault = market_keys.base_vault.to_string();
+        self.marketQuoteVault = market_keys.quote_vault.to_string();
+        self.marketBids = market_keys.bids.to_string();
+        self.marketAsks = market_keys.asks.to_string();
+        self.marketEventQueue = market_keys.event_queue.to_string();
+    }
+
+    /// Token program passed to the pool.
+    ///
+    /// The AMM v4 program only moves SPL Token mints, so pools with any
+    /// Token-2022 mint are refused.
+    pub fn token_program(&self) -> Result<Pubkey, Error> {
+        for token in [&self.mintA, &self.mintB, &self.mintLp] {
+            let program_id = token.program_id()?;
+            if program_id != spl_token::ID {
+                return Err(Error::UnsupportedToken(format!(
+                    "Mint {} is owned by {}, Raydium AMM v4 only supports SPL Token mints",
+                    token.label(),
+                    program_id
+                )));
+            }
+        }
+
+        Ok(spl_token::ID)
+    }
+
+    /// Lookup table published for the pool, if any.
+    pub fn lookup_tables(&self) -> Result<Vec<Pubkey>, Error> {
+        if self.lookupTableAccount.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![pubkey_from_str(&self.lookupTableAccount)?])
+    }
+
+    /// Account addresses by field name.
+    fn account_fields(&self) -> [(&'static str, &str); 18] {
+        [
+            ("programId", &self.programId),
+            ("id", &self.id),
+            ("mintA", &self.mintA.address),
+            ("mintB", &self.mintB.address),
+            ("vault.A", &self.vault.A),
+            ("vault.B", &self.vault.B),
+            ("authority", &self.authority),
+            ("openOrders", &self.openOrders),
+            ("targetOrders", &self.targetOrders),
+            ("mintLp", &self.mintLp.address),
+            ("marketProgramId", &self.marketProgramId),
+            ("marketId", &self.marketId),
+            ("marketAuthority", &self.marketAuthority),
+            ("marketBaseVault", &self.marketBaseVault),
+            ("marketQuoteVault", &self.marketQuoteVault),
+            ("marketBids", &self.marketBids),
+            ("marketAsks", &self.marketAsks),
+            ("marketEventQueue", &self.marketEventQueue),
+        ]
+    }
+
+    /// Account fields on which `self` and `other` disagree, as
+    /// `(field, self value, other value)`.
+    pub fn account_mismatches<'a>(
+        &'a self,
+        other: &'a PoolKeys,
+    ) -> Vec<(&'static str, &'a str, &'a str)> {
+        self.account_fields()
+            .into_iter()
+            .zip(other.account_fields())
+            .filter(|((_, a), (_, b))| a != b)
+            .map(|((field, a), (_, b))| (field, a, b))
+            .collect()
+    }
+}
+
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
+    pool_ids: &[String],
+    verify: bool,
+) -> Result<BTreeMap<String, PoolKeys>, Error> {
+    if verify {
+        return verify_pool_keys(raydium.api(), raydium.client(), pool_ids).await;
+    }
+
+    all_found(raydium.pool_source().pool_keys_by_ids(pool_ids).await?)
 }
 
-pub async fn fetch_pool_keys(config: &Config, pool_id: &str) -> Result<ApiResponse, Error> {
-    let url = format!("https://api-v3.raydium.io/pools/key/ids?ids={}", pool_id);
+/// Compare the Raydium API pool keys against the ones decoded on chain, with
+/// the chain accounts of all pools read in batches.
+async fn verify_pool_keys(
+    api: &ApiPoolSource,
+    client: &Client,
+    pool_ids: &[String],
+) -> Result<BTreeMap<String, PoolKeys>, Error> {
+    let api_pools = all_found(api.pool_keys_by_ids(pool_ids).await?)?;
+    let chain_pools = fetch_pool_keys_onchain_by_ids(client, pool_ids).await?;
 
-    debug!("Requesting URL: {}", url);
+    let mut pools = BTreeMap::new();
+    let mut mismatched = vec![];
+    for (pool_id, chain_keys) in chain_pools {
+        let chain_keys = chain_keys?;
+        let api_keys = &api_pools[&pool_id];
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| Error::Api(e.to_string()))?;
+        let mismatches = api_keys.account_mismatches(&chain_keys);
+        if mismatches.is_empty() {
+            info!("API pool keys of {} match the chain", pool_id);
+        } else {
+            for (field, api_value, chain_value) in &mismatches {
+                warn!(
+                    "{} {}: api {} != chain {}",
+                    pool_id, field, api_value, chain_value
+                );
+            }
+            mismatched.push(format!(
+                "{}: {}",
+                pool_id,
+                mismatches
+                    .iter()
+                    .map(|(field, _, _)| *field)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        pools.insert(pool_id, chain_keys);
+    }
 
-    if !response.status().is_success() {
-        return Err(Error::Api(format!("API error: {}", response.status())));
+    if !mismatched.is_empty() {
+        return Err(Error::PoolKeysMismatch(mismatched.join("; ")));
     }
 
-    // Get raw response and log it in debug mode
-    let text = response
-        .text()
-        .await
-        .map_err(|e| Error::Api(e.to_string()))?;
-    debug!("Raw response: {}", text);
+    Ok(pools)
+}
+
+/// Build pool keys from the AMM and market accounts.
+pub async fn fetch_pool_keys_onchain(client: &Client, pool_id: &str) -> Result<PoolKeys, Error> {
+    let (_, pool_keys) = fetch_pool_keys_onchain_by_ids(client, &[pool_id.to_string()])
+        .await?
+        .remove(0);
+    pool_keys
+}
+
+/// Build pool keys of every pool in `pool_ids`, reading the accounts of many
+/// pools per RPC call. Pools without an AMM account fail with
+/// `PoolNotFound`, without failing the others.
+pub async fn fetch_pool_keys_onchain_by_ids(
+    client: &Client,
+    pool_ids: &[String],
+) -> Result<Vec<(String, Result<PoolKeys, Error>)>, Error> {
+    let amm_ids = pool_ids
+        .iter()
+        .map(|pool_id| pubkey_from_str(pool_id))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let amm_accounts = get_accounts_chunked(client, &amm_ids).await?;
+    let amms = pool_ids
+        .iter()
+        .zip(&amm_ids)
+        .zip(&amm_accounts)
+        .map(|((pool_id, amm_id), account)| match account {
+            Some(account) => decode_amm(amm_id, account),
+            None => Err(Error::PoolNotFound(pool_id.clone())),
+        })
+        .collect::<Vec<_>>();
+
+    // Market and mints of every decoded pool, in the order of `amms`
+    let addresses = amms
+        .iter()
+        .flatten()
+        .flat_map(|amm| {
+            [
+                amm.market,
+                amm.coin_vault_mint,
+                amm.pc_vault_mint,
+                amm.lp_mint,
+            ]
+        })
+        .collect::<Vec<_>>();
+    let accounts = get_accounts_chunked(client, &addresses).await?;
+    let mut accounts = addresses.into_iter().zip(accounts);
+
+    Ok(pool_ids
+        .iter()
+        .zip(amm_ids)
+        .zip(amms)
+        .map(|((pool_id, amm_id), amm)| {
+            let pool_keys = amm.and_then(|amm_info| {
+                let accounts = accounts.by_ref().take(4).collect::<Vec<_>>();
+                pool_keys_from_accounts(&amm_id, &amm_info, &accounts)
+            });
+            (pool_id.clone(), pool_keys)
+        })
+        .collect())
+}
+
+async fn get_accounts_chunked(
+    client: &Client,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<Account>>, Error> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(ACCOUNTS_PER_REQUEST) {
+        accounts.extend(client.get_multiple_accounts_or_none(chunk).await?);
+    }
+    Ok(accounts)
+}
+
+fn decode_amm(amm_id: &Pubkey, account: &Account) -> Result<AmmInfo, Error> {
+    if account.owner != AMM_V4_PROGRAM_ID {
+        return Err(Error::AccountDecode(format!(
+            "AmmInfo: {} is owned by {}, not the AMM v4 program",
+            amm_id, account.owner
+        )));
+    }
+    let amm_info = AmmInfo::from_account_data(&account.data)?;
+    debug!("AmmInfo: {:?}", amm_info);
+    Ok(amm_info)
+}
+
+/// Pool keys of `amm_info` from its market, coin, pc and LP mint accounts.
+fn pool_keys_from_accounts(
+    amm_id: &Pubkey,
+    amm_info: &AmmInfo,
+    accounts: &[(Pubkey, Option<Account>)],
+) -> Result<PoolKeys, Error> {
+    let account = |index: usize| {
+        let (address, account) = &accounts[index];
+        account
+            .as_ref()
+            .ok_or_else(|| Error::RpcClient(format!("Account not found: {}", address)))
+    };
+    let market_keys =
+        market_keys_from_account(&accounts[0].0, &account(0)?.data, &amm_info.market_program)?;
+    debug!("MarketKeys: {:?}", market_keys);
+
+    let mut pool_keys = PoolKeys {
+        programId: AMM_V4_PROGRAM_ID.to_string(),
+        id: amm_id.to_string(),
+        mintA: token_info_from_mint(&amm_info.coin_vault_mint, account(1)?)?,
+        mintB: token_info_from_mint(&amm_info.pc_vault_mint, account(2)?)?,
+        lookupTableAccount: String::new(),
+        openTime: amm_info.state_data.pool_open_time.to_string(),
+        vault: VaultInfo {
+            A: amm_info.coin_vault.to_string(),
+            B: amm_info.pc_vault.to_string(),
+        },
+        authority: amm_info.authority(&AMM_V4_PROGRAM_ID)?.to_string(),
+        openOrders: amm_info.open_orders.to_string(),
+        targetOrders: amm_info.target_orders.to_string(),
+        mintLp: token_info_from_mint(&amm_info.lp_mint, account(3)?)?,
+        marketProgramId: String::new(),
+        marketId: String::new(),
+        marketAuthority: String::new(),
+        marketBaseVault: String::new(),
+        marketQuoteVault: String::new(),
+        marketBids: String::new(),
+        marketAsks: String::new(),
+        marketEventQueue: String::new(),
+    };
+    pool_keys.set_market_keys(&market_keys);
+
+    Ok(pool_keys)
+}
 
-    // Parse response
-    let pool: ApiResponse =
-        serde_json::from_str(&text).map_err(|e| Error::Api(format!("Parse error: {}", e)))?;
+fn token_info_from_mint(mint: &Pubkey, account: &Account) -> Result<TokenInfo, Error> {
+    let state = StateWithExtensions::<Mint>::unpack(&account.data)
+        .map_err(|e| Error::AccountDecode(format!("Mint {}: {}", mint, e)))?;
 
-    Ok(pool)
+    Ok(TokenInfo {
+        chainId: 101,
+        address: mint.to_string(),
+        programId: account.owner.to_string(),
+        logoURI: String::new(),
+        symbol: String::new(),
+        name: String::new(),
+        decimals: state.base.decimals as i32,
+        tags: vec![],
+        extensions: Value::Object(Default::default()),
+    })
 }
@@ -0,0 +1,227 @@
+use crate::{
+    error::Error,
+    math::PoolReserves,
+    pool_source::{ApiPoolSource, PoolSource},
+    raydium_v4::RaydiumV4Client,
+    state::amm_info::{AmmInfo, AMM_V4_PROGRAM_ID},
+    utils::{amount::amount_raw_to_display, client::Client, pubkey::pubkey_from_str},
+};
+use clap::ValueEnum;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Account as TokenAccount;
+use tracing::{debug, info, warn};
+
+/// Maximum number of pools whose vaults are read per RPC call.
+const POOLS_PER_REQUEST: usize = 50;
+
+/// Where `find-pools` looks for pools.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FindPoolsSource {
+    /// Raydium API, with TVL and volume
+    #[default]
+    Api,
+    /// AMM v4 program accounts, with reserves only
+    Rpc,
+}
+
+/// AMM v4 pool trading the requested mints, in the pool's base/quote order.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FoundPool {
+    pub pool_id: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub base_reserve: f64,
+    pub quote_reserve: f64,
+    /// In USD, unknown on chain
+    pub tvl: Option<f64>,
+    /// In USD, unknown on chain
+    pub volume_24h: Option<f64>,
+    pub fee_rate: f64,
+}
+
+impl FoundPool {
+    /// Reserve of `mint`, one of the pool mints.
+    fn reserve_of(&self, mint: &str) -> f64 {
+        if self.quote_mint == mint {
+            self.quote_reserve
+        } else {
+            self.base_reserve
+        }
+    }
+}
+
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
+    mint_a: &str,
+    mint_b: &str,
+    source: FindPoolsSource,
+) -> Result<Vec<FoundPool>, Error> {
+    let mint_a_key = pubkey_from_str(mint_a)?;
+    let mint_b_key = pubkey_from_str(mint_b)?;
+    if mint_a_key == mint_b_key {
+        return Err(Error::InvalidInput(
+            "--mint-a and --mint-b must differ".to_string(),
+        ));
+    }
+
+    let mut pools = match source {
+        FindPoolsSource::Api => find_pools_api(raydium.api(), mint_a, mint_b).await?,
+        FindPoolsSource::Rpc => {
+            find_pools_onchain(raydium.client(), &mint_a_key, &mint_b_key).await?
+        }
+    };
+    rank_pools(&mut pools, mint_b);
+
+    if pools.is_empty() {
+        info!("No AMM v4 pools found for {} / {}", mint_a, mint_b);
+    }
+    Ok(pools)
+}
+
+/// Order by TVL, then 24h volume, then the reserve of `mint_b`, largest first.
+pub fn rank_pools(pools: &mut [FoundPool], mint_b: &str) {
+    let key = |pool: &FoundPool| {
+        (
+            pool.tvl.unwrap_or(-1.0),
+            pool.volume_24h.unwrap_or(-1.0),
+            pool.reserve_of(mint_b),
+        )
+    };
+    pools.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        b.0.total_cmp(&a.0)
+            .then(b.1.total_cmp(&a.1))
+            .then(b.2.total_cmp(&a.2))
+    });
+}
+
+async fn find_pools_api(
+    api: &ApiPoolSource,
+    mint_a: &str,
+    mint_b: &str,
+) -> Result<Vec<FoundPool>, Error> {
+    let pools = api.pool_info_by_mints(mint_a, mint_b).await?;
+    debug!("API returned {} standard pools", pools.len());
+
+    let program_id = AMM_V4_PROGRAM_ID.to_string();
+    Ok(pools
+        .into_iter()
+        .filter(|pool| pool.programId == program_id)
+        .filter(|pool| {
+            let mints = [pool.mintA.address.as_str(), pool.mintB.address.as_str()];
+            mints == [mint_a, mint_b] || mints == [mint_b, mint_a]
+        })
+        .map(|pool| FoundPool {
+            pool_id: pool.id,
+            base_mint: pool.mintA.address,
+            quote_mint: pool.mintB.address,
+            base_reserve: pool.mintAmountA,
+            quote_reserve: pool.mintAmountB,
+            tvl: Some(pool.tvl),
+            volume_24h: Some(pool.day.volume),
+            fee_rate: pool.feeRate,
+        })
+        .collect())
+}
+
+/// Filters matching pools with `base` and `quote` as their mints.
+pub fn mint_filters(base: &Pubkey, quote: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::DataSize(AmmInfo::LEN as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            AmmInfo::COIN_MINT_OFFSET,
+            &base.to_bytes(),
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            AmmInfo::PC_MINT_OFFSET,
+            &quote.to_bytes(),
+        )),
+    ]
+}
+
+async fn find_pools_onchain(
+    client: &Client,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+) -> Result<Vec<FoundPool>, Error> {
+    let (forward, reverse) = tokio::try_join!(
+        client.get_program_accounts(&AMM_V4_PROGRAM_ID, mint_filters(mint_a, mint_b)),
+        client.get_program_accounts(&AMM_V4_PROGRAM_ID, mint_filters(mint_b, mint_a)),
+    )?;
+    // A pool that can't be read is skipped rather than failing the whole scan
+    let amms = forward
+        .into_iter()
+        .chain(reverse)
+        .filter_map(
+            |(pool_id, account)| match AmmInfo::from_account_data(&account.data) {
+                Ok(amm) => Some((pool_id, amm)),
+                Err(e) => {
+                    warn!("Skipping pool {}: {}", pool_id, e);
+                    None
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+    debug!("Found {} AMM v4 accounts", amms.len());
+
+    let mut pools = vec![];
+    for chunk in amms.chunks(POOLS_PER_REQUEST) {
+        let vaults = chunk
+            .iter()
+            .flat_map(|(_, amm)| [amm.coin_vault, amm.pc_vault])
+            .collect::<Vec<_>>();
+        let accounts = client.get_multiple_accounts_or_none(&vaults).await?;
+
+        for ((pool_id, amm), vaults) in chunk.iter().zip(accounts.chunks(2)) {
+            match found_pool(pool_id, amm, vaults) {
+                Ok(pool) => pools.push(pool),
+                Err(e) => warn!("Skipping pool {}: {}", pool_id, e),
+            }
+        }
+    }
+
+    Ok(pools)
+}
+
+/// Listing of the AMM `amm` at `pool_id` with its `vaults`, base then quote.
+pub fn found_pool(
+    pool_id: &Pubkey,
+    amm: &AmmInfo,
+    vaults: &[Option<Account>],
+) -> Result<FoundPool, Error> {
+    let unpack = |vault: &Option<Account>| {
+        let vault = vault
+            .as_ref()
+            .ok_or_else(|| Error::AccountDecode("Vault not found".to_string()))?;
+        TokenAccount::unpack(&vault.data)
+            .map(|vault| vault.amount)
+            .map_err(|e| Error::AccountDecode(format!("Vault: {}", e)))
+    };
+    // Order book funds are left out, they only matter for ranking
+    let reserves = PoolReserves::new(
+        unpack(&vaults[0])?,
+        unpack(&vaults[1])?,
+        amm.state_data.need_take_pnl_coin,
+        amm.state_data.need_take_pnl_pc,
+    )?;
+    let display = |amount: u64, decimals: u64| {
+        amount_raw_to_display(amount, decimals as i32)
+            .to_f64()
+            .unwrap_or_default()
+    };
+
+    Ok(FoundPool {
+        pool_id: pool_id.to_string(),
+        base_mint: amm.coin_vault_mint.to_string(),
+        quote_mint: amm.pc_vault_mint.to_string(),
+        base_reserve: display(reserves.base, amm.coin_decimals),
+        quote_reserve: display(reserves.quote, amm.pc_decimals),
+        tvl: None,
+        volume_24h: None,
+        fee_rate: amm.fees.swap_fee_numerator as f64 / amm.fees.swap_fee_denominator.max(1) as f64,
+    })
+}
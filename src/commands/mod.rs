@@ -1,4 +1,22 @@
 pub mod add_liquidity;
 pub mod fetch_pool_info;
 pub mod fetch_pool_keys;
+pub mod find_pools;
+pub mod positions;
 pub mod remove_liquidity;
+pub mod round_trip;
+pub mod swap;
+pub mod zap_in;
+pub mod zap_out;
+
+use crate::utils::client::TransactionResult;
+use serde::Serialize;
+
+/// Result of a write command: the quote it was built from and the
+/// transaction that was sent or simulated.
+#[derive(Debug, Serialize)]
+pub struct CommandResult<Q> {
+    pub pool_id: String,
+    pub quote: Q,
+    pub transaction: TransactionResult,
+}
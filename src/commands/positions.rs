@@ -0,0 +1,107 @@
+use crate::{
+    commands::fetch_pool_info::PoolInfo,
+    error::Error,
+    pool_source::{ApiPoolSource, PoolSource},
+    raydium_v4::RaydiumV4Client,
+    utils::{
+        amount::amount_raw_to_display,
+        client::{Client, TokenBalance},
+    },
+};
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+/// Maximum number of LP mints looked up per API request.
+const LP_MINTS_PER_REQUEST: usize = 100;
+
+/// LP position held by the payer, valued from the Raydium API.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Position {
+    pub pool_id: String,
+    pub lp_mint: String,
+    pub token_account: String,
+    pub lp_amount: f64,
+    pub base_symbol: String,
+    pub base_amount: f64,
+    pub quote_symbol: String,
+    pub quote_amount: f64,
+    pub value_usd: f64,
+    /// Share of the LP supply, in percent
+    pub pool_share_percentage: f64,
+    /// Fee APR over the last day, in percent
+    pub fee_apr: f64,
+}
+
+impl Position {
+    fn new(balance: &TokenBalance, pool: &PoolInfo) -> Self {
+        let lp_amount = amount_raw_to_display(balance.amount, balance.decimals)
+            .to_f64()
+            .unwrap_or_default();
+        let share = if pool.lpAmount > 0.0 {
+            lp_amount / pool.lpAmount
+        } else {
+            0.0
+        };
+
+        Self {
+            pool_id: pool.id.clone(),
+            lp_mint: balance.mint.to_string(),
+            token_account: balance.address.to_string(),
+            lp_amount,
+            base_symbol: pool.mintA.symbol.clone(),
+            base_amount: pool.mintAmountA * share,
+            quote_symbol: pool.mintB.symbol.clone(),
+            quote_amount: pool.mintAmountB * share,
+            value_usd: lp_amount * pool.lpPrice,
+            pool_share_percentage: share * 100.0,
+            fee_apr: pool.day.feeApr,
+        }
+    }
+}
+
+pub async fn execute<S: PoolSource>(raydium: &RaydiumV4Client<S>) -> Result<Vec<Position>, Error> {
+    let positions = fetch_positions(raydium.api(), raydium.client()).await?;
+
+    if positions.is_empty() {
+        info!("No Raydium LP positions found");
+    } else {
+        info!(
+            "Total value: ${:.2}",
+            positions.iter().map(|p| p.value_usd).sum::<f64>()
+        );
+    }
+    Ok(positions)
+}
+
+/// Match the payer's token accounts against Raydium LP mints.
+pub async fn fetch_positions(api: &ApiPoolSource, client: &Client) -> Result<Vec<Position>, Error> {
+    let balances = client.get_token_balances().await?;
+    debug!("Token accounts with a balance: {}", balances.len());
+
+    let mut positions = vec![];
+    for chunk in balances.chunks(LP_MINTS_PER_REQUEST) {
+        let lp_mints = chunk
+            .iter()
+            .map(|balance| balance.mint.to_string())
+            .collect::<Vec<_>>();
+        let response = api.pool_info_by_lp_mints(&lp_mints).await?;
+
+        // Mints that aren't LP mints come back as null entries
+        let pools = response
+            .data
+            .into_iter()
+            .flatten()
+            .map(|pool| (pool.lpMint.address.clone(), pool))
+            .collect::<HashMap<_, _>>();
+        for balance in chunk {
+            if let Some(pool) = pools.get(&balance.mint.to_string()) {
+                positions.push(Position::new(balance, pool));
+            }
+        }
+    }
+
+    positions.sort_by(|a, b| b.value_usd.total_cmp(&a.value_usd));
+    Ok(positions)
+}
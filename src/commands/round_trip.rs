@@ -0,0 +1,94 @@
+use crate::{
+    commands::{add_liquidity::fixed_side_for_mint, CommandResult},
+    error::Error,
+    instructions::add_liquidity::FixedSide,
+    pool_source::PoolSource,
+    raydium_v4::{RaydiumV4Client, RoundTripQuote},
+    utils::{
+        amount::{amount_display_to_raw, amount_raw_to_display},
+        client::WsolOptions,
+        confirmation::get_confirmation,
+        guardrails::Trade,
+        pubkey::pubkey_from_str,
+    },
+};
+
+use rust_decimal::prelude::ToPrimitive;
+use tracing::{debug, info};
+
+/// Add liquidity and burn the LP it mints in a single transaction.
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
+    pool_id: &str,
+    mint_pubkey: &str,
+    amount: f64,
+    slippage_percentage: f64,
+    wsol: WsolOptions,
+    dry_run: bool,
+    yes: bool,
+) -> Result<CommandResult<RoundTripQuote>, Error> {
+    let pool_keys = raydium.pool_keys(pool_id).await?;
+
+    let fixed_side = fixed_side_for_mint(&pool_keys, &pubkey_from_str(mint_pubkey)?)?;
+    let (input_decimals, other_decimals) = match fixed_side {
+        FixedSide::Base => (pool_keys.mintA.decimals, pool_keys.mintB.decimals),
+        FixedSide::Quote => (pool_keys.mintB.decimals, pool_keys.mintA.decimals),
+    };
+    let input_amount_raw = amount_display_to_raw(amount, input_decimals)
+        .to_u64()
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid amount: {}", amount)))?;
+    debug!("Input Amount Raw: {}", input_amount_raw);
+
+    let quote = raydium
+        .quote_round_trip(
+            &pool_keys,
+            fixed_side,
+            input_amount_raw,
+            slippage_percentage,
+        )
+        .await?;
+    let transaction = raydium.build_round_trip(&pool_keys, &quote, wsol).await?;
+
+    let confirmation_msg = format!(
+        "Round Trip: add {} {} and {} {} (min other {}) for {} LP, then remove {} LP for minimum {} {} and {} {}",
+        amount_raw_to_display(quote.add.deposit.base_amount, pool_keys.mintA.decimals),
+        pool_keys.mintA.label(),
+        amount_raw_to_display(quote.add.deposit.quote_amount, pool_keys.mintB.decimals),
+        pool_keys.mintB.label(),
+        amount_raw_to_display(quote.add.other_amount_min, other_decimals),
+        amount_raw_to_display(quote.add.deposit.lp_amount, pool_keys.mintLp.decimals),
+        amount_raw_to_display(quote.remove.lp_amount, pool_keys.mintLp.decimals),
+        amount_raw_to_display(quote.remove.base_amount_min, pool_keys.mintA.decimals),
+        pool_keys.mintA.label(),
+        amount_raw_to_display(quote.remove.quote_amount_min, pool_keys.mintB.decimals),
+        pool_keys.mintB.label(),
+    );
+
+    get_confirmation(
+        raydium.config(),
+        raydium.pool_source(),
+        &confirmation_msg,
+        &Trade {
+            pool_keys: &pool_keys,
+            slippage_percentage,
+            base_amount: quote.add.max_base_amount,
+            quote_amount: quote.add.max_quote_amount,
+        },
+        yes,
+        dry_run,
+    )
+    .await?;
+
+    let transaction = raydium.execute(&transaction, dry_run).await?;
+    if let Some(signature) = &transaction.signature {
+        info!("Transaction sent with signature: {}", signature);
+    } else {
+        info!("Transaction simulated, nothing sent");
+    }
+
+    Ok(CommandResult {
+        pool_id: pool_keys.id.clone(),
+        quote,
+        transaction,
+    })
+}
@@ -0,0 +1,137 @@
+use crate::{
+    commands::{fetch_pool_keys::PoolKeys, CommandResult},
+    error::Error,
+    math::SwapDirection,
+    pool_source::PoolSource,
+    raydium_v4::{self, ExactSwapQuote, RaydiumV4Client},
+    utils::{
+        amount::{amount_display_to_raw, amount_raw_to_display},
+        client::WsolOptions,
+        confirmation::get_confirmation,
+        guardrails::Trade,
+        pubkey::pubkey_from_str,
+    },
+};
+
+use rust_decimal::prelude::ToPrimitive;
+use solana_sdk::pubkey::Pubkey;
+use tracing::info;
+
+/// Side of a swap given exactly, in display units.
+#[derive(Clone, Copy, Debug)]
+pub enum SwapAmount {
+    ExactIn(f64),
+    ExactOut(f64),
+}
+
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
+    pool_id: &str,
+    input_mint_pubkey: &str,
+    amount: SwapAmount,
+    slippage_percentage: f64,
+    wsol: WsolOptions,
+    dry_run: bool,
+    yes: bool,
+) -> Result<CommandResult<ExactSwapQuote>, Error> {
+    let pool_keys = raydium.pool_keys(pool_id).await?;
+
+    let direction = swap_direction_for_mint(&pool_keys, &pubkey_from_str(input_mint_pubkey)?)?;
+    let (token_in, token_out) = match direction {
+        SwapDirection::BaseToQuote => (&pool_keys.mintA, &pool_keys.mintB),
+        SwapDirection::QuoteToBase => (&pool_keys.mintB, &pool_keys.mintA),
+    };
+
+    let amount = match amount {
+        SwapAmount::ExactIn(amount_in) => raydium_v4::SwapAmount::ExactIn(
+            amount_display_to_raw(amount_in, token_in.decimals)
+                .to_u64()
+                .ok_or_else(|| Error::InvalidInput(format!("Invalid amount in: {}", amount_in)))?,
+        ),
+        SwapAmount::ExactOut(amount_out) => raydium_v4::SwapAmount::ExactOut(
+            amount_display_to_raw(amount_out, token_out.decimals)
+                .to_u64()
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!("Invalid amount out: {}", amount_out))
+                })?,
+        ),
+    };
+
+    let quote = raydium
+        .quote_swap(&pool_keys, direction, amount, slippage_percentage)
+        .await?;
+    let transaction = raydium.build_swap(&pool_keys, &quote, wsol).await?;
+
+    let confirmation_msg = match amount {
+        raydium_v4::SwapAmount::ExactIn(_) => format!(
+            "Swap: {} {} for {} {} (minimum {}, fee {} {})",
+            amount_raw_to_display(quote.swap.amount_in, token_in.decimals),
+            token_in.label(),
+            amount_raw_to_display(quote.swap.amount_out, token_out.decimals),
+            token_out.label(),
+            amount_raw_to_display(quote.min_amount_out(), token_out.decimals),
+            amount_raw_to_display(quote.swap.fee, token_in.decimals),
+            token_in.label(),
+        ),
+        raydium_v4::SwapAmount::ExactOut(_) => format!(
+            "Swap: {} {} (maximum {}, fee {} {}) for {} {}",
+            amount_raw_to_display(quote.swap.amount_in, token_in.decimals),
+            token_in.label(),
+            amount_raw_to_display(quote.max_amount_in(), token_in.decimals),
+            amount_raw_to_display(quote.swap.fee, token_in.decimals),
+            token_in.label(),
+            amount_raw_to_display(quote.swap.amount_out, token_out.decimals),
+            token_out.label(),
+        ),
+    };
+
+    let (base_amount, quote_amount) = match direction {
+        SwapDirection::BaseToQuote => (quote.max_amount_in(), 0),
+        SwapDirection::QuoteToBase => (0, quote.max_amount_in()),
+    };
+
+    get_confirmation(
+        raydium.config(),
+        raydium.pool_source(),
+        &confirmation_msg,
+        &Trade {
+            pool_keys: &pool_keys,
+            slippage_percentage,
+            base_amount,
+            quote_amount,
+        },
+        yes,
+        dry_run,
+    )
+    .await?;
+
+    let transaction = raydium.execute(&transaction, dry_run).await?;
+    if let Some(signature) = &transaction.signature {
+        info!("Transaction sent with signature: {}", signature);
+    } else {
+        info!("Transaction simulated, nothing sent");
+    }
+
+    Ok(CommandResult {
+        pool_id: pool_keys.id.clone(),
+        quote,
+        transaction,
+    })
+}
+
+/// Direction of a swap selling `mint`.
+pub(crate) fn swap_direction_for_mint(
+    pool_keys: &PoolKeys,
+    mint: &Pubkey,
+) -> Result<SwapDirection, Error> {
+    if mint.to_string() == pool_keys.mintA.address {
+        Ok(SwapDirection::BaseToQuote)
+    } else if mint.to_string() == pool_keys.mintB.address {
+        Ok(SwapDirection::QuoteToBase)
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Mint {} is not in pool {}",
+            mint, pool_keys.id
+        )))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    commands::{swap::swap_direction_for_mint, CommandResult},
+    error::Error,
+    math::SwapDirection,
+    pool_source::PoolSource,
+    raydium_v4::{RaydiumV4Client, ZapInQuote},
+    utils::{
+        amount::{amount_display_to_raw, amount_raw_to_display},
+        client::WsolOptions,
+        confirmation::get_confirmation,
+        guardrails::Trade,
+        pubkey::pubkey_from_str,
+    },
+};
+
+use rust_decimal::prelude::ToPrimitive;
+use tracing::info;
+
+/// Swap part of a single-sided amount and deposit both sides in a single
+/// transaction.
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
+    pool_id: &str,
+    mint_pubkey: &str,
+    amount: f64,
+    slippage_percentage: f64,
+    wsol: WsolOptions,
+    dry_run: bool,
+    yes: bool,
+) -> Result<CommandResult<ZapInQuote>, Error> {
+    let pool_keys = raydium.pool_keys(pool_id).await?;
+
+    let direction = swap_direction_for_mint(&pool_keys, &pubkey_from_str(mint_pubkey)?)?;
+    let (token_in, token_out) = match direction {
+        SwapDirection::BaseToQuote => (&pool_keys.mintA, &pool_keys.mintB),
+        SwapDirection::QuoteToBase => (&pool_keys.mintB, &pool_keys.mintA),
+    };
+
+    let input_amount_raw = amount_display_to_raw(amount, token_in.decimals)
+        .to_u64()
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid amount: {}", amount)))?;
+
+    let quote = raydium
+        .quote_zap_in(&pool_keys, direction, input_amount_raw, slippage_percentage)
+        .await?;
+    let transaction = raydium.build_zap_in(&pool_keys, &quote, wsol).await?;
+
+    let confirmation_msg = format!(
+        "Zap In: swap {} {} for {} {} (minimum {}), then add {} {} and {} {} for {} LP (leftover {} {} and {} {})",
+        amount_raw_to_display(quote.swap.swap.amount_in, token_in.decimals),
+        token_in.label(),
+        amount_raw_to_display(quote.swap.swap.amount_out, token_out.decimals),
+        token_out.label(),
+        amount_raw_to_display(quote.swap.min_amount_out(), token_out.decimals),
+        amount_raw_to_display(quote.add.deposit.base_amount, pool_keys.mintA.decimals),
+        pool_keys.mintA.label(),
+        amount_raw_to_display(quote.add.deposit.quote_amount, pool_keys.mintB.decimals),
+        pool_keys.mintB.label(),
+        amount_raw_to_display(quote.add.deposit.lp_amount, pool_keys.mintLp.decimals),
+        amount_raw_to_display(quote.leftover_in, token_in.decimals),
+        token_in.label(),
+        amount_raw_to_display(quote.leftover_out, token_out.decimals),
+        token_out.label(),
+    );
+
+    let (base_amount, quote_amount) = match direction {
+        SwapDirection::BaseToQuote => (input_amount_raw, 0),
+        SwapDirection::QuoteToBase => (0, input_amount_raw),
+    };
+
+    get_confirmation(
+        raydium.config(),
+        raydium.pool_source(),
+        &confirmation_msg,
+        &Trade {
+            pool_keys: &pool_keys,
+            slippage_percentage,
+            base_amount,
+            quote_amount,
+        },
+        yes,
+        dry_run,
+    )
+    .await?;
+
+    let transaction = raydium.execute(&transaction, dry_run).await?;
+    if let Some(signature) = &transaction.signature {
+        info!("Transaction sent with signature: {}", signature);
+    } else {
+        info!("Transaction simulated, nothing sent");
+    }
+
+    Ok(CommandResult {
+        pool_id: pool_keys.id.clone(),
+        quote,
+        transaction,
+    })
+}
@@ -0,0 +1,113 @@
+use crate::{
+    commands::{swap::swap_direction_for_mint, CommandResult},
+    error::Error,
+    math::SwapDirection,
+    pool_source::PoolSource,
+    raydium_v4::{RaydiumV4Client, ZapOutQuote},
+    utils::{
+        amount::{amount_display_to_raw, amount_raw_to_display},
+        client::WsolOptions,
+        confirmation::get_confirmation,
+        guardrails::Trade,
+        pubkey::pubkey_from_str,
+    },
+};
+
+use rust_decimal::prelude::ToPrimitive;
+use tracing::info;
+
+/// Burn LP and swap the side that isn't `mint_pubkey` into it in a single
+/// transaction.
+pub async fn execute<S: PoolSource>(
+    raydium: &RaydiumV4Client<S>,
+    pool_id: &str,
+    lp_amount: f64,
+    mint_pubkey: &str,
+    slippage_percentage: f64,
+    wsol: WsolOptions,
+    dry_run: bool,
+    yes: bool,
+) -> Result<CommandResult<ZapOutQuote>, Error> {
+    let pool_keys = raydium.pool_keys(pool_id).await?;
+
+    // The swap sells the side we don't want to keep
+    let direction = match swap_direction_for_mint(&pool_keys, &pubkey_from_str(mint_pubkey)?)? {
+        SwapDirection::BaseToQuote => SwapDirection::QuoteToBase,
+        SwapDirection::QuoteToBase => SwapDirection::BaseToQuote,
+    };
+    let (token_in, token_out) = match direction {
+        SwapDirection::BaseToQuote => (&pool_keys.mintA, &pool_keys.mintB),
+        SwapDirection::QuoteToBase => (&pool_keys.mintB, &pool_keys.mintA),
+    };
+
+    let lp_amount_raw = amount_display_to_raw(lp_amount, pool_keys.mintLp.decimals)
+        .to_u64()
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid LP amount: {}", lp_amount)))?;
+
+    let quote = raydium
+        .quote_zap_out(&pool_keys, lp_amount_raw, direction, slippage_percentage)
+        .await?;
+    let transaction = raydium.build_zap_out(&pool_keys, &quote, wsol).await?;
+
+    let withdraw = &quote.remove.withdraw;
+    let target_amount = match direction {
+        SwapDirection::BaseToQuote => withdraw.quote_amount,
+        SwapDirection::QuoteToBase => withdraw.base_amount,
+    };
+
+    let confirmation_msg = format!(
+        "Zap Out: remove {} LP for {} {} and {} {} (minimum {} {} and {} {}), then swap {} {} for {} {} (minimum {}), settling about {} {}",
+        amount_raw_to_display(lp_amount_raw, pool_keys.mintLp.decimals),
+        amount_raw_to_display(withdraw.base_amount, pool_keys.mintA.decimals),
+        pool_keys.mintA.label(),
+        amount_raw_to_display(withdraw.quote_amount, pool_keys.mintB.decimals),
+        pool_keys.mintB.label(),
+        amount_raw_to_display(quote.remove.base_amount_min, pool_keys.mintA.decimals),
+        pool_keys.mintA.label(),
+        amount_raw_to_display(quote.remove.quote_amount_min, pool_keys.mintB.decimals),
+        pool_keys.mintB.label(),
+        amount_raw_to_display(quote.swap.swap.amount_in, token_in.decimals),
+        token_in.label(),
+        amount_raw_to_display(quote.swap.swap.amount_out, token_out.decimals),
+        token_out.label(),
+        amount_raw_to_display(quote.swap.min_amount_out(), token_out.decimals),
+        amount_raw_to_display(target_amount + quote.swap.swap.amount_out, token_out.decimals),
+        token_out.label(),
+    );
+
+    if quote.leftover_in > 0 {
+        info!(
+            "About {} {} may be left unsold",
+            amount_raw_to_display(quote.leftover_in, token_in.decimals),
+            token_in.label()
+        );
+    }
+
+    get_confirmation(
+        raydium.config(),
+        raydium.pool_source(),
+        &confirmation_msg,
+        &Trade {
+            pool_keys: &pool_keys,
+            slippage_percentage,
+            base_amount: withdraw.base_amount,
+            quote_amount: withdraw.quote_amount,
+        },
+        yes,
+        dry_run,
+    )
+    .await?;
+
+    let transaction = raydium.execute(&transaction, dry_run).await?;
+    if let Some(signature) = &transaction.signature {
+        info!("Transaction sent with signature: {}", signature);
+    } else {
+        info!("Transaction simulated, nothing sent");
+    }
+
+    Ok(CommandResult {
+        pool_id: pool_keys.id.clone(),
+        quote,
+        transaction,
+    })
+}
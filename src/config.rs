@@ -1,4 +1,8 @@
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    pool_source::PoolSourceConfig,
+    utils::{client::WsolMode, compute_budget::PriorityFeeConfig, guardrails::GuardrailsConfig},
+};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -7,6 +11,34 @@ pub struct Config {
     pub rpc_endpoint: String,
     pub api_key: String,
     pub keypair_path: PathBuf,
+    /// Fall back to the Raydium API when pool keys can't be decoded on chain
+    #[serde(default = "default_api_fallback")]
+    pub api_fallback: bool,
+    /// Address lookup tables used to compress every transaction
+    #[serde(default)]
+    pub lookup_tables: Vec<String>,
+    #[serde(default)]
+    pub priority_fee: PriorityFeeConfig,
+    /// Headroom added to the simulated compute units
+    #[serde(default = "default_compute_unit_margin_percentage")]
+    pub compute_unit_margin_percentage: u32,
+    /// How SOL is wrapped unless a command overrides it
+    #[serde(default)]
+    pub wsol_mode: WsolMode,
+    /// Limits enforced on write commands run with `--yes`
+    #[serde(default)]
+    pub guardrails: GuardrailsConfig,
+    /// Where pool keys and pool info are loaded from
+    #[serde(default)]
+    pub pool_source: PoolSourceConfig,
+}
+
+fn default_api_fallback() -> bool {
+    true
+}
+
+fn default_compute_unit_margin_percentage() -> u32 {
+    10
 }
 
 impl Config {
@@ -25,6 +57,13 @@ impl Config {
             rpc_endpoint: "https://api.mainnet-beta.solana.com".to_string(),
             api_key: "".to_string(),
             keypair_path: PathBuf::from("./keypair.json"),
+            api_fallback: default_api_fallback(),
+            lookup_tables: vec![],
+            priority_fee: PriorityFeeConfig::default(),
+            compute_unit_margin_percentage: default_compute_unit_margin_percentage(),
+            wsol_mode: WsolMode::default(),
+            guardrails: GuardrailsConfig::default(),
+            pool_source: PoolSourceConfig::default(),
         }
     }
 }
@@ -16,6 +16,12 @@ pub enum Error {
     #[error("RPC client error: {0}")]
     RpcClient(String),
 
+    #[error("Simulation failed: {err}\n{}", .logs.join("\n"))]
+    Simulation { err: String, logs: Vec<String> },
+
+    #[error("Transaction error: {0}")]
+    Transaction(String),
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
@@ -25,12 +31,55 @@ pub enum Error {
     #[error("Invalid token account: {0}")]
     InvalidTokenAccount(String),
 
+    #[error("Account decode error: {0}")]
+    AccountDecode(String),
+
+    #[error("Pool keys mismatch: {0}")]
+    PoolKeysMismatch(String),
+
+    #[error("Unsupported token: {0}")]
+    UnsupportedToken(String),
+
     #[error("API error: {0}")]
     Api(String),
 
+    #[error("Pool not found: {0}")]
+    PoolNotFound(String),
+
+    #[error("API rejected the request: {msg}")]
+    ApiRejected { msg: String },
+
+    #[error("API response doesn't match the expected schema at {field}")]
+    SchemaMismatch { field: String },
+
     #[error("Insufficient balance: {0}")]
     InsufficientBalance(String),
 
+    #[error("Guardrail violated: {0}")]
+    Guardrail(String),
+
+    #[error("Cancelled by user")]
+    Cancelled,
+
+    #[error("Output error: {0}")]
+    Output(String),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
+
+impl Error {
+    /// Process exit code reported for the error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Config(_) | Error::Keypair(_) | Error::InvalidInput(_) => 2,
+            Error::PoolNotFound(_) => 3,
+            Error::ApiRejected { .. } => 4,
+            Error::SchemaMismatch { .. } => 5,
+            Error::Api(_) => 6,
+            Error::Guardrail(_) => 7,
+            Error::Cancelled => 8,
+            _ => 1,
+        }
+    }
+}
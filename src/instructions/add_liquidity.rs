@@ -1,8 +1,8 @@
+use serde::Serialize;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
 };
-use spl_token::ID as TOKEN_PROGRAM_ID;
 use tracing::debug;
 
 use crate::error::Error;
@@ -32,7 +32,8 @@ pub struct IxUserKeys {
     pub owner: Pubkey,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FixedSide {
     Base,
     Quote,
@@ -48,15 +49,15 @@ pub fn make_add_liquidity_instruction(
     data.push(3u8); // instruction discriminator
     data.extend_from_slice(&params.base_amount_in.to_le_bytes());
     data.extend_from_slice(&params.quote_amount_in.to_le_bytes());
-    data.extend_from_slice(&params.other_amount_min.to_le_bytes());
     data.extend_from_slice(&match params.fixed_side {
         FixedSide::Base => 0u64.to_le_bytes(),
         FixedSide::Quote => 1u64.to_le_bytes(),
     });
+    data.extend_from_slice(&params.other_amount_min.to_le_bytes());
 
     // Create account metas
     let mut keys = vec![
-        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(params.rpc_pool_keys.token_program()?, false),
         AccountMeta::new(pubkey_from_str(&params.rpc_pool_keys.id)?, false),
         AccountMeta::new_readonly(pubkey_from_str(&params.rpc_pool_keys.authority)?, false),
         AccountMeta::new_readonly(pubkey_from_str(&params.rpc_pool_keys.openOrders)?, false),
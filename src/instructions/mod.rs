@@ -1,2 +1,3 @@
 pub mod add_liquidity;
 pub mod remove_liquidity;
+pub mod swap;
@@ -3,7 +3,6 @@ use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
 };
-use spl_token::ID as TOKEN_PROGRAM_ID;
 
 pub struct RemoveLiquidityInstructionParams<'a> {
     pub rpc_pool_keys: &'a PoolKeys,
@@ -41,7 +40,7 @@ pub fn make_remove_liquidity_instruction(
     // Build account metas
     let mut keys = vec![
         // System
-        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(rpc_pool_keys.token_program()?, false),
         // AMM accounts
         AccountMeta::new(pubkey_from_str(&rpc_pool_keys.id)?, false),
         AccountMeta::new_readonly(pubkey_from_str(&rpc_pool_keys.authority)?, false),
@@ -0,0 +1,94 @@
+use crate::{commands::fetch_pool_keys::PoolKeys, error::Error, utils::pubkey::pubkey_from_str};
+use serde::Serialize;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Amounts of a swap, keyed by the side that is exact.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapAmounts {
+    /// Sell exactly `amount_in`, receiving at least `minimum_amount_out`
+    BaseIn {
+        amount_in: u64,
+        minimum_amount_out: u64,
+    },
+    /// Buy exactly `amount_out`, spending at most `max_amount_in`
+    BaseOut { max_amount_in: u64, amount_out: u64 },
+}
+
+pub struct SwapInstructionParams<'a> {
+    pub rpc_pool_keys: &'a PoolKeys,
+    pub user_keys: &'a IxUserKeys,
+    pub amounts: SwapAmounts,
+}
+
+#[derive(Debug)]
+pub struct IxUserKeys {
+    pub source_token_account: Pubkey,
+    pub destination_token_account: Pubkey,
+    pub owner: Pubkey,
+}
+
+pub fn make_swap_instruction(params: SwapInstructionParams) -> Result<Instruction, Error> {
+    let SwapInstructionParams {
+        rpc_pool_keys,
+        user_keys,
+        amounts,
+    } = params;
+
+    // Create instruction data buffer
+    let mut data = Vec::with_capacity(17); // 1 + (2 * 8)
+    match amounts {
+        SwapAmounts::BaseIn {
+            amount_in,
+            minimum_amount_out,
+        } => {
+            data.push(9u8); // SwapBaseIn discriminator
+            data.extend_from_slice(&amount_in.to_le_bytes());
+            data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        }
+        SwapAmounts::BaseOut {
+            max_amount_in,
+            amount_out,
+        } => {
+            data.push(11u8); // SwapBaseOut discriminator
+            data.extend_from_slice(&max_amount_in.to_le_bytes());
+            data.extend_from_slice(&amount_out.to_le_bytes());
+        }
+    }
+
+    // The direction is given by the user accounts, both instructions take the
+    // same accounts
+    let keys = vec![
+        // System
+        AccountMeta::new_readonly(rpc_pool_keys.token_program()?, false),
+        // AMM accounts
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.id)?, false),
+        AccountMeta::new_readonly(pubkey_from_str(&rpc_pool_keys.authority)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.openOrders)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.targetOrders)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.vault.A)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.vault.B)?, false),
+        // Serum market accounts
+        AccountMeta::new_readonly(pubkey_from_str(&rpc_pool_keys.marketProgramId)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.marketId)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.marketBids)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.marketAsks)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.marketEventQueue)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.marketBaseVault)?, false),
+        AccountMeta::new(pubkey_from_str(&rpc_pool_keys.marketQuoteVault)?, false),
+        AccountMeta::new_readonly(pubkey_from_str(&rpc_pool_keys.marketAuthority)?, false),
+        // User accounts
+        AccountMeta::new(user_keys.source_token_account, false),
+        AccountMeta::new(user_keys.destination_token_account, false),
+        AccountMeta::new_readonly(user_keys.owner, true),
+    ];
+
+    Ok(Instruction {
+        program_id: pubkey_from_str(&rpc_pool_keys.programId)?,
+        accounts: keys,
+        data,
+    })
+}
@@ -3,50 +3,97 @@ pub mod commands;
 pub mod config;
 pub mod error;
 pub mod instructions;
+pub mod math;
+pub mod pool_source;
+pub mod raydium_v4;
+pub mod state;
 pub mod utils;
 
-use cli::{Cli, Command};
+pub use raydium_v4::RaydiumV4Client;
+
+use cli::{Cli, Command, PoolIdArgs, WsolArgs};
 use config::Config;
 use error::Result;
 use tracing::info;
-use utils::client::get_client;
+use utils::{
+    client::{WsolMode, WsolOptions},
+    printer::print_output,
+};
 
 pub async fn run(cli: Cli) -> Result<()> {
     // Load config if specified
-    let config = if let Some(path) = cli.config {
+    let mut config = if let Some(path) = cli.config {
         Config::from_file(path)?
     } else {
         Config::default()
     };
 
-    let client = get_client(&config)?;
+    // Command line overrides
+    if let Some(strategy) = cli.priority_fee_strategy {
+        config.priority_fee.strategy = strategy;
+    }
+    if let Some(min_priority_fee) = cli.min_priority_fee {
+        config.priority_fee.min_micro_lamports = min_priority_fee;
+    }
+    if let Some(max_priority_fee) = cli.max_priority_fee {
+        config.priority_fee.max_micro_lamports = max_priority_fee;
+    }
+    if let Some(api_base_url) = cli.api_base_url {
+        config.pool_source.api_base_url = api_base_url;
+    }
+
+    let raydium = RaydiumV4Client::new(config)?;
+    let config = raydium.config();
+    let client = raydium.client();
 
     // Execute the requested command
     match cli.command {
-        Command::FetchPoolInfo { pool_id } => {
-            info!("Fetching pool {}", pool_id);
-            commands::fetch_pool_info::execute(&config, &pool_id).await
+        Command::FetchPoolInfo { pools } => {
+            let pool_ids = pool_ids(pools)?;
+            info!("Fetching {} pools", pool_ids.len());
+            let result = commands::fetch_pool_info::execute(&raydium, &pool_ids).await?;
+            print_output(&result, cli.output)
+        }
+        Command::FetchPoolKeys { pools, verify } => {
+            let pool_ids = pool_ids(pools)?;
+            info!("Fetching pool keys of {} pools", pool_ids.len());
+            let result = commands::fetch_pool_keys::execute(&raydium, &pool_ids, verify).await?;
+            print_output(&result, cli.output)
+        }
+        Command::FindPools {
+            mint_a,
+            mint_b,
+            source,
+        } => {
+            info!("Finding pools for {} / {}", mint_a, mint_b);
+            let result = commands::find_pools::execute(&raydium, &mint_a, &mint_b, source).await?;
+            print_output(&result, cli.output)
         }
-        Command::FetchPoolKeys { pool_id } => {
-            info!("Fetching pool keys {}", pool_id);
-            commands::fetch_pool_keys::execute(&config, &pool_id).await
+        Command::Positions => {
+            info!("Fetching positions of {}", client.get_payer_pubkey());
+            let result = commands::positions::execute(&raydium).await?;
+            print_output(&result, cli.output)
         }
         Command::AddLiquidity {
             pool_id,
             mint_pubkey,
             amount,
             slippage_percentage,
+            wsol,
         } => {
             info!("Adding liquidity to pool {}", pool_id);
-            commands::add_liquidity::execute(
-                &config,
-                &client,
+            let result = commands::add_liquidity::execute(
+                &raydium,
                 &pool_id,
                 &mint_pubkey,
                 amount,
                 slippage_percentage,
+                wsol_options(config, wsol)?,
+                cli.dry_run,
+                cli.yes,
             )
-            .await
+            .await?;
+            print_output(&result, cli.output)
         }
         Command::RemoveLiquidity {
             pool_id,
@@ -54,18 +101,157 @@ pub async fn run(cli: Cli) -> Result<()> {
             slippage_percentage,
             base_amount_min,
             quote_amount_min,
+            wsol,
         } => {
             info!("Removing liquidity from pool {}", pool_id);
-            commands::remove_liquidity::execute(
-                &config,
-                &client,
+            let result = commands::remove_liquidity::execute(
+                &raydium,
                 &pool_id,
                 lp_amount,
                 slippage_percentage,
                 base_amount_min,
                 quote_amount_min,
+                wsol_options(config, wsol)?,
+                cli.dry_run,
+                cli.yes,
+            )
+            .await?;
+            print_output(&result, cli.output)
+        }
+        Command::RoundTrip {
+            pool_id,
+            mint_pubkey,
+            amount,
+            slippage_percentage,
+            wsol,
+        } => {
+            info!("Round-tripping liquidity in pool {}", pool_id);
+            let result = commands::round_trip::execute(
+                &raydium,
+                &pool_id,
+                &mint_pubkey,
+                amount,
+                slippage_percentage,
+                wsol_options(config, wsol)?,
+                cli.dry_run,
+                cli.yes,
+            )
+            .await?;
+            print_output(&result, cli.output)
+        }
+        Command::Swap {
+            pool_id,
+            mint_pubkey,
+            amount_in,
+            amount_out,
+            slippage_percentage,
+            wsol,
+        } => {
+            info!("Swapping through pool {}", pool_id);
+            let amount = match (amount_in, amount_out) {
+                (Some(amount_in), None) => commands::swap::SwapAmount::ExactIn(amount_in),
+                (None, Some(amount_out)) => commands::swap::SwapAmount::ExactOut(amount_out),
+                _ => {
+                    return Err(error::Error::InvalidInput(
+                        "Give exactly one of --amount-in and --amount-out".to_string(),
+                    ))
+                }
+            };
+            let result = commands::swap::execute(
+                &raydium,
+                &pool_id,
+                &mint_pubkey,
+                amount,
+                slippage_percentage,
+                wsol_options(config, wsol)?,
+                cli.dry_run,
+                cli.yes,
+            )
+            .await?;
+            print_output(&result, cli.output)
+        }
+        Command::ZapIn {
+            pool_id,
+            mint_pubkey,
+            amount,
+            slippage_percentage,
+            wsol,
+        } => {
+            info!("Zapping into pool {}", pool_id);
+            let result = commands::zap_in::execute(
+                &raydium,
+                &pool_id,
+                &mint_pubkey,
+                amount,
+                slippage_percentage,
+                wsol_options(config, wsol)?,
+                cli.dry_run,
+                cli.yes,
             )
-            .await
+            .await?;
+            print_output(&result, cli.output)
         }
+        Command::ZapOut {
+            pool_id,
+            lp_amount,
+            mint_pubkey,
+            slippage_percentage,
+            wsol,
+        } => {
+            info!("Zapping out of pool {}", pool_id);
+            let result = commands::zap_out::execute(
+                &raydium,
+                &pool_id,
+                lp_amount,
+                &mint_pubkey,
+                slippage_percentage,
+                wsol_options(config, wsol)?,
+                cli.dry_run,
+                cli.yes,
+            )
+            .await?;
+            print_output(&result, cli.output)
+        }
+    }
+}
+
+/// Pool ids given on the command line followed by the ones in the file,
+/// without duplicates.
+fn pool_ids(args: PoolIdArgs) -> Result<Vec<String>> {
+    let mut pool_ids = args.pool_id;
+    if let Some(path) = args.pool_ids_file {
+        let contents = std::fs::read_to_string(&path).map_err(|e| {
+            error::Error::InvalidInput(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        pool_ids.extend(
+            contents
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
     }
+
+    let mut seen = std::collections::HashSet::new();
+    pool_ids.retain(|pool_id| seen.insert(pool_id.clone()));
+    if pool_ids.is_empty() {
+        return Err(error::Error::InvalidInput("No pool ids given".to_string()));
+    }
+
+    Ok(pool_ids)
+}
+
+/// WSOL handling for a command, falling back to the config mode.
+fn wsol_options(config: &Config, args: WsolArgs) -> Result<WsolOptions> {
+    let mode = args.wsol_mode.unwrap_or(config.wsol_mode);
+    if args.keep_wsol && mode == WsolMode::TempAccount {
+        return Err(error::Error::InvalidInput(
+            "--keep-wsol requires the ata WSOL mode".to_string(),
+        ));
+    }
+
+    Ok(WsolOptions {
+        mode,
+        keep: args.keep_wsol,
+    })
 }
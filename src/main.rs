@@ -1,10 +1,10 @@
 use clap::Parser;
 use mantis_raydium_client::cli::Cli;
-use mantis_raydium_client::error::Result;
-use tracing::info;
+use std::process::ExitCode;
+use tracing::{error, info};
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     // Parse command line arguments first
     let cli = Cli::parse();
 
@@ -13,7 +13,13 @@ async fn main() -> Result<()> {
 
     // Run the application
     info!("Starting application");
-    mantis_raydium_client::run(cli).await
+    match mantis_raydium_client::run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
 }
 
 fn setup_logging(debug: bool) {
@@ -28,5 +34,6 @@ fn setup_logging(debug: bool) {
         .with_target(false) // Removes the target from the output
         .with_thread_ids(false) // Removes thread IDs from the output
         .with_line_number(true) // Includes line numbers
+        .with_writer(std::io::stderr) // Keeps stdout for the command result
         .init();
 }
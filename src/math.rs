@@ -0,0 +1,535 @@
+//! Integer reproduction of the Raydium AMM v4 liquidity math.
+//!
+//! Reserves are taken net of the PnL the pool has already booked. The PnL the
+//! program books from fees earned since the last deposit or withdrawal, right
+//! before it acts, is not modelled.
+
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal, RoundingStrategy,
+};
+use serde::Serialize;
+
+use crate::{error::Error, instructions::add_liquidity::FixedSide};
+
+/// Pool reserves net of the PnL the protocol has yet to take, as used by the
+/// program for every deposit, withdrawal and swap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoolReserves {
+    pub base: u64,
+    pub quote: u64,
+}
+
+impl PoolReserves {
+    /// `base_total` and `quote_total` are the vault balances plus any funds
+    /// the pool holds on the order book.
+    pub fn new(
+        base_total: u64,
+        quote_total: u64,
+        need_take_pnl_base: u64,
+        need_take_pnl_quote: u64,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            base: base_total
+                .checked_sub(need_take_pnl_base)
+                .ok_or_else(|| Error::Math("Base PnL exceeds reserves".to_string()))?,
+            quote: quote_total
+                .checked_sub(need_take_pnl_quote)
+                .ok_or_else(|| Error::Math("Quote PnL exceeds reserves".to_string()))?,
+        })
+    }
+
+    /// Reserves once `quote` has been deposited.
+    pub fn after_deposit(&self, quote: &DepositQuote) -> Result<Self, Error> {
+        Ok(Self {
+            base: self
+                .base
+                .checked_add(quote.base_amount)
+                .ok_or_else(|| Error::Math("Overflow in base reserve".to_string()))?,
+            quote: self
+                .quote
+                .checked_add(quote.quote_amount)
+                .ok_or_else(|| Error::Math("Overflow in quote reserve".to_string()))?,
+        })
+    }
+}
+
+/// Amounts the program takes and mints for a deposit.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct DepositQuote {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub lp_amount: u64,
+}
+
+/// Compute a proportional deposit of `amount` on `fixed_side`.
+///
+/// The other side is rounded up and the LP amount down, as the program does.
+/// Before it deposits, the program books a share of the pool's growth since
+/// the last deposit or withdrawal as PnL. That isn't modelled here, so the
+/// amounts may be off by the booked share and bounds have to allow for it.
+pub fn deposit(
+    reserves: &PoolReserves,
+    lp_supply: u64,
+    fixed_side: FixedSide,
+    amount: u64,
+) -> Result<DepositQuote, Error> {
+    if reserves.base == 0 || reserves.quote == 0 || lp_supply == 0 {
+        return Err(Error::Math("Pool has no liquidity".to_string()));
+    }
+
+    let (fixed_reserve, other_reserve) = match fixed_side {
+        FixedSide::Base => (reserves.base, reserves.quote),
+        FixedSide::Quote => (reserves.quote, reserves.base),
+    };
+
+    let other_amount = to_u64(ceil_div(
+        amount as u128 * other_reserve as u128,
+        fixed_reserve as u128,
+    )?)?;
+    let lp_amount = to_u64(amount as u128 * lp_supply as u128 / fixed_reserve as u128)?;
+
+    if lp_amount == 0 {
+        return Err(Error::Math("Deposit too small to mint LP".to_string()));
+    }
+
+    Ok(match fixed_side {
+        FixedSide::Base => DepositQuote {
+            base_amount: amount,
+            quote_amount: other_amount,
+            lp_amount,
+        },
+        FixedSide::Quote => DepositQuote {
+            base_amount: other_amount,
+            quote_amount: amount,
+            lp_amount,
+        },
+    })
+}
+
+/// Least LP a deposit of `amount` on `fixed_side` mints while the program
+/// still takes at least `other_amount_min` of the other side.
+///
+/// The deposit mints the least when a sale of the fixed side lands first, so
+/// this is the LP at the largest such sale the bound accepts, found by
+/// bisection on the exact integer swap. Even with no slippage the other side
+/// is rounded up, so a small sale still passes and the LP comes out below the
+/// quote. Fees booked by further trades before the deposit lands are not
+/// covered.
+pub fn deposit_lp_min(
+    reserves: &PoolReserves,
+    lp_supply: u64,
+    fee: SwapFee,
+    fixed_side: FixedSide,
+    amount: u64,
+    other_amount_min: u64,
+) -> Result<u64, Error> {
+    let quoted = deposit(reserves, lp_supply, fixed_side, amount)?;
+    let direction = match fixed_side {
+        FixedSide::Base => SwapDirection::BaseToQuote,
+        FixedSide::Quote => SwapDirection::QuoteToBase,
+    };
+
+    // Deposit after selling `amount_in` of the fixed side, if the bound accepts
+    // it. A larger sale only lowers the other side the deposit takes.
+    let landed = |amount_in: u64| -> Result<Option<DepositQuote>, Error> {
+        // A sale too small to output anything can't land
+        let Ok(swap) = swap_base_in(reserves, fee, direction, amount_in) else {
+            return Ok(Some(quoted));
+        };
+        let landed = deposit(
+            &reserves.after_swap(direction, &swap)?,
+            lp_supply,
+            fixed_side,
+            amount,
+        )?;
+        let other_amount = match fixed_side {
+            FixedSide::Base => landed.quote_amount,
+            FixedSide::Quote => landed.base_amount,
+        };
+        Ok((other_amount >= other_amount_min).then_some(landed))
+    };
+
+    let (fixed_reserve, _) = reserves.swap_sides(direction);
+    let (mut low, mut high) = (0, fixed_reserve);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if landed(mid)?.is_some() {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok(landed(low)?.map_or(quoted.lp_amount, |landed| {
+        landed.lp_amount.min(quoted.lp_amount)
+    }))
+}
+
+/// Amounts the program returns for burning LP.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct WithdrawQuote {
+    pub lp_amount: u64,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+/// Compute the pro-rata share of the reserves returned for `lp_amount`,
+/// rounded down on both sides.
+pub fn withdraw(
+    reserves: &PoolReserves,
+    lp_supply: u64,
+    lp_amount: u64,
+) -> Result<WithdrawQuote, Error> {
+    if lp_amount > lp_supply {
+        return Err(Error::Math(format!(
+            "LP amount {} exceeds supply {}",
+            lp_amount, lp_supply
+        )));
+    }
+
+    let base_amount = to_u64(lp_amount as u128 * reserves.base as u128 / lp_supply as u128)?;
+    let quote_amount = to_u64(lp_amount as u128 * reserves.quote as u128 / lp_supply as u128)?;
+
+    if base_amount == 0 || quote_amount == 0 {
+        return Err(Error::Math("Withdrawal too small".to_string()));
+    }
+
+    Ok(WithdrawQuote {
+        lp_amount,
+        base_amount,
+        quote_amount,
+    })
+}
+
+/// Direction of a swap through the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapDirection {
+    BaseToQuote,
+    QuoteToBase,
+}
+
+/// Fee the program charges on the input of a swap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SwapFee {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl SwapFee {
+    /// The 0.25% fee charged by Raydium AMM v4 pools.
+    pub const DEFAULT: SwapFee = SwapFee {
+        numerator: 25,
+        denominator: 10_000,
+    };
+}
+
+/// Amounts moved by a swap, with `fee` taken out of `amount_in`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+}
+
+impl PoolReserves {
+    /// Reserves once `quote` has been withdrawn.
+    pub fn after_withdraw(&self, quote: &WithdrawQuote) -> Result<Self, Error> {
+        Ok(Self {
+            base: self
+                .base
+                .checked_sub(quote.base_amount)
+                .ok_or_else(|| Error::Math("Withdrawal exceeds base reserve".to_string()))?,
+            quote: self
+                .quote
+                .checked_sub(quote.quote_amount)
+                .ok_or_else(|| Error::Math("Withdrawal exceeds quote reserve".to_string()))?,
+        })
+    }
+
+    /// Reserves once `quote` has been swapped in `direction`. The fee stays
+    /// in the pool.
+    pub fn after_swap(&self, direction: SwapDirection, quote: &SwapQuote) -> Result<Self, Error> {
+        let overflow = || Error::Math("Overflow in swap reserves".to_string());
+        Ok(match direction {
+            SwapDirection::BaseToQuote => Self {
+                base: self
+                    .base
+                    .checked_add(quote.amount_in)
+                    .ok_or_else(overflow)?,
+                quote: self
+                    .quote
+                    .checked_sub(quote.amount_out)
+                    .ok_or_else(overflow)?,
+            },
+            SwapDirection::QuoteToBase => Self {
+                base: self
+                    .base
+                    .checked_sub(quote.amount_out)
+                    .ok_or_else(overflow)?,
+                quote: self
+                    .quote
+                    .checked_add(quote.amount_in)
+                    .ok_or_else(overflow)?,
+            },
+        })
+    }
+
+    /// Reserves as `(input, output)` for `direction`.
+    fn swap_sides(&self, direction: SwapDirection) -> (u64, u64) {
+        match direction {
+            SwapDirection::BaseToQuote => (self.base, self.quote),
+            SwapDirection::QuoteToBase => (self.quote, self.base),
+        }
+    }
+}
+
+/// Output of selling exactly `amount_in`, as computed by `SwapBaseIn`.
+///
+/// The fee is rounded up and taken from the input before the constant
+/// product, whose output is rounded down.
+pub fn swap_base_in(
+    reserves: &PoolReserves,
+    fee: SwapFee,
+    direction: SwapDirection,
+    amount_in: u64,
+) -> Result<SwapQuote, Error> {
+    let (reserve_in, reserve_out) = reserves.swap_sides(direction);
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(Error::Math("Pool has no liquidity".to_string()));
+    }
+
+    let fee_amount = to_u64(ceil_div(
+        amount_in as u128 * fee.numerator as u128,
+        fee.denominator as u128,
+    )?)?;
+    let amount_in_after_fee = amount_in
+        .checked_sub(fee_amount)
+        .ok_or_else(|| Error::Math("Fee exceeds swap amount".to_string()))?;
+
+    let amount_out = to_u64(
+        reserve_out as u128 * amount_in_after_fee as u128
+            / (reserve_in as u128 + amount_in_after_fee as u128),
+    )?;
+
+    if amount_out == 0 {
+        return Err(Error::Math("Swap too small".to_string()));
+    }
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out,
+        fee: fee_amount,
+    })
+}
+
+/// Input needed to buy exactly `amount_out`, as computed by `SwapBaseOut`.
+///
+/// The constant product input and the fee gross-up are both rounded up.
+pub fn swap_base_out(
+    reserves: &PoolReserves,
+    fee: SwapFee,
+    direction: SwapDirection,
+    amount_out: u64,
+) -> Result<SwapQuote, Error> {
+    let (reserve_in, reserve_out) = reserves.swap_sides(direction);
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(Error::Math("Pool has no liquidity".to_string()));
+    }
+    if amount_out == 0 || amount_out >= reserve_out {
+        return Err(Error::Math(format!(
+            "Swap output {} must be between 1 and the reserve {}",
+            amount_out, reserve_out
+        )));
+    }
+    if fee.numerator >= fee.denominator {
+        return Err(Error::Math("Invalid swap fee".to_string()));
+    }
+
+    let amount_in_before_fee = ceil_div(
+        reserve_in as u128 * amount_out as u128,
+        (reserve_out - amount_out) as u128,
+    )?;
+    let amount_in = to_u64(ceil_div(
+        amount_in_before_fee * fee.denominator as u128,
+        (fee.denominator - fee.numerator) as u128,
+    )?)?;
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out,
+        fee: amount_in - to_u64(amount_in_before_fee)?,
+    })
+}
+
+/// Swap and deposit making up a single-sided deposit.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct ZapInSplit {
+    pub swap: SwapQuote,
+    /// Least swap output the slippage allows
+    pub minimum_amount_out: u64,
+    /// Always the output side of the swap
+    pub fixed_side: FixedSide,
+    pub deposit: DepositQuote,
+    /// Input tokens left over after the deposit
+    pub leftover_in: u64,
+    /// Swapped tokens left over after the deposit
+    pub leftover_out: u64,
+}
+
+/// Split `amount` of the input side of `direction` into a swap and a
+/// deposit so that the swapped tokens and the rest match the pool ratio.
+///
+/// The swapped amount is the smallest `s` with
+/// `out / (Q - out) >= (amount - s) / (R + s)` on the pool curve with the fee
+/// taken from `s`, found by bisection on the exact integer swap.
+///
+/// Only `minimum_amount_out` of the swap is sure to land, so the deposit is
+/// fixed on the output side at no more than that and no more than the rest of
+/// the input can match, and quoted on the post-swap reserves. With no slippage
+/// only rounding dust is left over; otherwise the slippage share of the output.
+/// The PnL the program books from the swap fee before the deposit is not
+/// modelled and is left to the slippage.
+pub fn zap_in(
+    reserves: &PoolReserves,
+    lp_supply: u64,
+    fee: SwapFee,
+    direction: SwapDirection,
+    amount: u64,
+    slippage_percentage: Decimal,
+) -> Result<ZapInSplit, Error> {
+    let (reserve_in, reserve_out) = reserves.swap_sides(direction);
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(Error::Math("Pool has no liquidity".to_string()));
+    }
+    if fee.numerator >= fee.denominator {
+        return Err(Error::Math("Invalid swap fee".to_string()));
+    }
+    if amount < 2 {
+        return Err(Error::Math("Zap amount too small".to_string()));
+    }
+
+    // Whether swapping `swap_amount` gives at least what the rest of the input
+    // needs at the post-swap ratio. More input only raises the output and the
+    // input reserve while lowering the rest, so this is monotonic.
+    let covers = |swap_amount: u64| -> Result<bool, Error> {
+        // The only error left is a swap too small to output anything
+        let Ok(swap) = swap_base_in(reserves, fee, direction, swap_amount) else {
+            return Ok(false);
+        };
+        let (reserve_in_after, reserve_out_after) =
+            reserves.after_swap(direction, &swap)?.swap_sides(direction);
+        Ok(swap.amount_out as u128 * reserve_in_after as u128
+            >= (amount - swap_amount) as u128 * reserve_out_after as u128)
+    };
+
+    // Smallest swap covering the rest, bisected on the exact integer swap
+    let (mut low, mut high) = (1, amount - 1);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if covers(mid)? {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    let swap_amount = low;
+
+    let swap = swap_base_in(reserves, fee, direction, swap_amount)?;
+    let minimum_amount_out = min_amount_with_slippage(swap.amount_out, slippage_percentage)?;
+    let reserves_after = reserves.after_swap(direction, &swap)?;
+    let (reserve_in_after, reserve_out_after) = reserves_after.swap_sides(direction);
+    let remaining = amount - swap_amount;
+
+    let fixed_side = match direction {
+        SwapDirection::BaseToQuote => FixedSide::Quote,
+        SwapDirection::QuoteToBase => FixedSide::Base,
+    };
+    let fixed_amount = to_u64(
+        (remaining as u128 * reserve_out_after as u128 / reserve_in_after as u128)
+            .min(minimum_amount_out as u128),
+    )?;
+
+    let deposit = deposit(&reserves_after, lp_supply, fixed_side, fixed_amount)
+        .map_err(|_| Error::Math("Zap amount too small to deposit".to_string()))?;
+    let needed_in = match fixed_side {
+        FixedSide::Base => deposit.quote_amount,
+        FixedSide::Quote => deposit.base_amount,
+    };
+    if needed_in > remaining {
+        return Err(Error::Math("Zap amount too small to deposit".to_string()));
+    }
+
+    Ok(ZapInSplit {
+        swap,
+        minimum_amount_out,
+        fixed_side,
+        deposit,
+        leftover_in: remaining - needed_in,
+        leftover_out: swap.amount_out - fixed_amount,
+    })
+}
+
+/// Lower `amount` by `slippage_percentage`, rounding down.
+pub fn min_amount_with_slippage(amount: u64, slippage_percentage: Decimal) -> Result<u64, Error> {
+    let coefficient = slippage_coefficient(slippage_percentage, false)?;
+    scale(amount, coefficient, RoundingStrategy::ToZero)
+}
+
+/// Raise `amount` by `slippage_percentage`, rounding up.
+pub fn max_amount_with_slippage(amount: u64, slippage_percentage: Decimal) -> Result<u64, Error> {
+    let coefficient = slippage_coefficient(slippage_percentage, true)?;
+    scale(amount, coefficient, RoundingStrategy::AwayFromZero)
+}
+
+fn slippage_coefficient(slippage_percentage: Decimal, upward: bool) -> Result<Decimal, Error> {
+    if slippage_percentage.is_sign_negative() || slippage_percentage > Decimal::ONE_HUNDRED {
+        return Err(Error::InvalidInput(format!(
+            "Slippage must be between 0 and 100, got {}",
+            slippage_percentage
+        )));
+    }
+
+    let slippage = slippage_percentage / Decimal::ONE_HUNDRED;
+    Ok(if upward {
+        Decimal::ONE + slippage
+    } else {
+        Decimal::ONE - slippage
+    })
+}
+
+fn scale(amount: u64, coefficient: Decimal, strategy: RoundingStrategy) -> Result<u64, Error> {
+    Decimal::from_u64(amount)
+        .and_then(|amount| amount.checked_mul(coefficient))
+        .map(|scaled| scaled.round_dp_with_strategy(0, strategy))
+        .and_then(|scaled| scaled.to_u64())
+        .ok_or_else(|| Error::Math(format!("Overflow scaling {}", amount)))
+}
+
+/// Ceiling division as implemented by the program.
+///
+/// Unlike a plain ceiling, a quotient below one rounds to the nearest
+/// integer, so dust amounts may map to zero.
+pub fn ceil_div(dividend: u128, divisor: u128) -> Result<u128, Error> {
+    if divisor == 0 {
+        return Err(Error::Math("Division by zero".to_string()));
+    }
+
+    let quotient = dividend / divisor;
+    if quotient == 0 {
+        return Ok(if dividend >= divisor - dividend { 1 } else { 0 });
+    }
+
+    Ok(if dividend % divisor > 0 {
+        quotient + 1
+    } else {
+        quotient
+    })
+}
+
+fn to_u64(value: u128) -> Result<u64, Error> {
+    value
+        .try_into()
+        .map_err(|_| Error::Math("Overflow converting to u64".to_string()))
+}
@@ -0,0 +1,226 @@
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::{
+    commands::{
+        fetch_pool_info::{LpApiResponse, PoolInfo},
+        fetch_pool_keys::PoolKeys,
+    },
+    error::Error,
+    pool_source::{single, PoolSource, PoolsById},
+};
+
+/// Maximum number of pool ids looked up per API request.
+const IDS_PER_REQUEST: usize = 100;
+
+/// Maximum number of pools per page of the by-mint endpoint.
+const POOLS_PER_PAGE: usize = 100;
+
+/// Time allowed to connect to the API.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for a whole API request, so unattended runs can't hang.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Envelope of the by-mint endpoint.
+#[derive(Deserialize)]
+struct MintApiResponse {
+    data: PoolPage,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PoolPage {
+    data: Vec<PoolInfo>,
+    #[serde(default)]
+    has_next_page: bool,
+}
+
+/// Envelope of the by-id endpoints.
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    data: Vec<Option<T>>,
+}
+
+/// Entry of the by-id endpoints, naming the pool it answers for.
+trait ById {
+    fn pool_id(&self) -> &str;
+}
+
+impl ById for PoolKeys {
+    fn pool_id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl ById for PoolInfo {
+    fn pool_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Raydium HTTP API, sharing one connection pool across requests.
+#[derive(Debug, Clone)]
+pub struct ApiPoolSource {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl ApiPoolSource {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("HTTP client"),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let url = format!("{}{}", self.base_url, path);
+
+        debug!("Requesting URL: {}", url);
+
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| Error::Api(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(Error::Api(format!("API error: {}", response.status())));
+        }
+
+        // Get raw response and log it in debug mode
+        let text = response
+            .text()
+            .await
+            .map_err(|e| Error::Api(e.to_string()))?;
+        debug!("Raw response: {}", text);
+
+        let value: Value =
+            serde_json::from_str(&text).map_err(|e| Error::Api(format!("Parse error: {}", e)))?;
+        if value.get("success").and_then(Value::as_bool) == Some(false) {
+            return Err(Error::ApiRejected {
+                msg: value
+                    .get("msg")
+                    .and_then(Value::as_str)
+                    .unwrap_or("no message")
+                    .to_string(),
+            });
+        }
+
+        serde_path_to_error::deserialize(value).map_err(|e| {
+            warn!("Unexpected API response: {}", e);
+            Error::SchemaMismatch {
+                field: e.path().to_string(),
+            }
+        })
+    }
+
+    /// One request for at most `IDS_PER_REQUEST` pools, keyed by id.
+    async fn request_by_ids<T: DeserializeOwned + ById>(
+        &self,
+        path: &str,
+        pool_ids: &[String],
+    ) -> Result<PoolsById<T>, Error> {
+        let response: ApiResponse<T> = self
+            .get(&format!("{}?ids={}", path, pool_ids.join(",")))
+            .await?;
+
+        // Entries are matched by the id they carry rather than their position,
+        // ids the API leaves out or answers with null stay not found
+        let mut pools = pool_ids
+            .iter()
+            .map(|pool_id| (pool_id.clone(), None))
+            .collect::<PoolsById<T>>();
+        for pool in response.data.into_iter().flatten() {
+            let pool_id = pool.pool_id().to_string();
+            match pools.get_mut(&pool_id) {
+                Some(entry) => *entry = Some(pool),
+                None => {
+                    return Err(Error::Api(format!(
+                        "API returned pool {} which wasn't requested",
+                        pool_id
+                    )))
+                }
+            }
+        }
+
+        Ok(pools)
+    }
+
+    /// Every pool in `pool_ids`, split into requests the API accepts.
+    async fn chunked_by_ids<T: DeserializeOwned + ById>(
+        &self,
+        path: &str,
+        pool_ids: &[String],
+    ) -> Result<PoolsById<T>, Error> {
+        let mut pools = PoolsById::new();
+        for chunk in pool_ids.chunks(IDS_PER_REQUEST) {
+            pools.extend(self.request_by_ids(path, chunk).await?);
+        }
+        Ok(pools)
+    }
+
+    /// Standard pools trading `mint_a` against `mint_b`, in either order.
+    pub async fn pool_info_by_mints(
+        &self,
+        mint_a: &str,
+        mint_b: &str,
+    ) -> Result<Vec<PoolInfo>, Error> {
+        let mut pools = vec![];
+        for page in 1.. {
+            let response: MintApiResponse = self
+                .get(&format!(
+                    "/pools/info/mint?mint1={}&mint2={}&poolType=standard&poolSortField=liquidity&sortType=desc&pageSize={}&page={}",
+                    mint_a, mint_b, POOLS_PER_PAGE, page
+                ))
+                .await?;
+            pools.extend(response.data.data);
+            if !response.data.has_next_page {
+                break;
+            }
+        }
+        Ok(pools)
+    }
+
+    /// Pools by LP mint, `None` for mints that aren't Raydium LP mints.
+    pub async fn pool_info_by_lp_mints(&self, lp_mints: &[String]) -> Result<LpApiResponse, Error> {
+        self.get(&format!("/pools/info/lps?lps={}", lp_mints.join(",")))
+            .await
+    }
+}
+
+impl PoolSource for ApiPoolSource {
+    async fn pool_keys(&self, pool_id: &str) -> Result<PoolKeys, Error> {
+        single(
+            self.pool_keys_by_ids(&[pool_id.to_string()]).await?,
+            pool_id,
+        )
+    }
+
+    async fn pool_info(&self, pool_id: &str) -> Result<PoolInfo, Error> {
+        single(
+            self.pool_info_by_ids(&[pool_id.to_string()]).await?,
+            pool_id,
+        )
+    }
+
+    async fn pool_keys_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolKeys>, Error> {
+        self.chunked_by_ids("/pools/key/ids", pool_ids).await
+    }
+
+    async fn pool_info_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolInfo>, Error> {
+        self.chunked_by_ids("/pools/info/ids", pool_ids).await
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{de::DeserializeOwned, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    commands::{fetch_pool_info::PoolInfo, fetch_pool_keys::PoolKeys},
+    error::Error,
+    pool_source::{single, PoolSource, PoolsById},
+};
+
+/// On-disk JSON cache in front of another source. Pool keys are kept for
+/// good once they are complete, pool info only for `info_ttl`. Only keys and
+/// info are cached; quoting still reads the pool reserves over RPC.
+pub struct CachePoolSource<S> {
+    dir: PathBuf,
+    info_ttl: Duration,
+    inner: S,
+}
+
+impl<S: PoolSource> CachePoolSource<S> {
+    pub fn new(dir: &Path, info_ttl: Duration, inner: S) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            info_ttl,
+            inner,
+        }
+    }
+
+    /// Source behind the cache.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Entry of `pool_id`, which must be a pubkey so it can't escape `dir`.
+    fn path(&self, pool_id: &str, kind: &str) -> Result<PathBuf, Error> {
+        let pool_id = Pubkey::from_str(pool_id)
+            .map_err(|e| Error::InvalidInput(format!("Invalid pool id {}: {}", pool_id, e)))?;
+        Ok(self.dir.join(format!("{}.{}.json", pool_id, kind)))
+    }
+
+    /// Cached value at `path`, unless it is missing, unreadable or older than
+    /// `ttl`.
+    async fn read<T: DeserializeOwned>(path: &Path, ttl: Option<Duration>) -> Option<T> {
+        if let Some(ttl) = ttl {
+            let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > ttl {
+                debug!("Cache entry {} expired", path.display());
+                return None;
+            }
+        }
+
+        let contents = tokio::fs::read(path).await.ok()?;
+        match serde_json::from_slice(&contents) {
+            Ok(value) => {
+                debug!("Cache hit {}", path.display());
+                Some(value)
+            }
+            Err(e) => {
+                warn!("Ignoring unreadable cache entry {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Cached entries of `pool_ids`, and the ids that have none.
+    async fn cached<T: DeserializeOwned>(
+        &self,
+        pool_ids: &[String],
+        kind: &str,
+        ttl: Option<Duration>,
+    ) -> Result<(PoolsById<T>, Vec<String>), Error> {
+        let mut pools = PoolsById::new();
+        let mut missing = vec![];
+        for pool_id in pool_ids {
+            match Self::read(&self.path(pool_id, kind)?, ttl).await {
+                Some(pool) => {
+                    pools.insert(pool_id.clone(), Some(pool));
+                }
+                None => missing.push(pool_id.clone()),
+            }
+        }
+        Ok((pools, missing))
+    }
+
+    /// Store the pools that were found and pass `keep`, the rest are asked
+    /// again next time.
+    async fn store<T: Serialize>(
+        &self,
+        pools: &PoolsById<T>,
+        kind: &str,
+        keep: impl Fn(&T) -> bool,
+    ) -> Result<(), Error> {
+        for (pool_id, pool) in pools {
+            if let Some(pool) = pool.as_ref().filter(|pool| keep(pool)) {
+                self.write(&self.path(pool_id, kind)?, pool).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Store `value` at `path`. Failing to write only costs a refetch.
+    async fn write<T: Serialize>(&self, path: &Path, value: &T) {
+        let result = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            let contents = serde_json::to_vec_pretty(value)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            tokio::fs::write(path, contents).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            warn!("Failed writing cache entry {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl<S: PoolSource> PoolSource for CachePoolSource<S> {
+    async fn pool_keys(&self, pool_id: &str) -> Result<PoolKeys, Error> {
+        single(
+            self.pool_keys_by_ids(&[pool_id.to_string()]).await?,
+            pool_id,
+        )
+    }
+
+    async fn pool_info(&self, pool_id: &str) -> Result<PoolInfo, Error> {
+        single(
+            self.pool_info_by_ids(&[pool_id.to_string()]).await?,
+            pool_id,
+        )
+    }
+
+    async fn pool_keys_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolKeys>, Error> {
+        let (mut pools, missing) = self.cached(pool_ids, "keys", None).await?;
+        if !missing.is_empty() {
+            let fetched = self.inner.pool_keys_by_ids(&missing).await?;
+            // Keys without their lookup table are refetched until the source
+            // has one, rather than kept without it for good
+            self.store(&fetched, "keys", |keys| !keys.lookupTableAccount.is_empty())
+                .await?;
+            pools.extend(fetched);
+        }
+        Ok(pools)
+    }
+
+    async fn pool_info_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolInfo>, Error> {
+        let (mut pools, missing) = self.cached(pool_ids, "info", Some(self.info_ttl)).await?;
+        if !missing.is_empty() {
+            let fetched = self.inner.pool_info_by_ids(&missing).await?;
+            self.store(&fetched, "info", |_| true).await?;
+            pools.extend(fetched);
+        }
+        Ok(pools)
+    }
+}
@@ -0,0 +1,195 @@
+//! Where pool keys and pool info come from.
+
+mod api;
+mod cache;
+mod rpc;
+
+pub use api::ApiPoolSource;
+pub use cache::CachePoolSource;
+pub use rpc::RpcPoolSource;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, future::Future, path::PathBuf, sync::Arc};
+
+use crate::{
+    commands::{fetch_pool_info::PoolInfo, fetch_pool_keys::PoolKeys},
+    config::Config,
+    error::Error,
+    utils::client::Client,
+};
+
+/// Pools keyed by id, `None` for ids the source doesn't know.
+pub type PoolsById<T> = BTreeMap<String, Option<T>>;
+
+/// Source of pool keys and pool info.
+pub trait PoolSource: Sync {
+    /// Keys needed to build instructions for `pool_id`.
+    fn pool_keys(&self, pool_id: &str) -> impl Future<Output = Result<PoolKeys, Error>> + Send;
+
+    /// Prices, TVL and volume of `pool_id`.
+    fn pool_info(&self, pool_id: &str) -> impl Future<Output = Result<PoolInfo, Error>> + Send;
+
+    /// Keys of every pool in `pool_ids`, one at a time unless the source
+    /// can batch them.
+    fn pool_keys_by_ids(
+        &self,
+        pool_ids: &[String],
+    ) -> impl Future<Output = Result<PoolsById<PoolKeys>, Error>> + Send {
+        async move {
+            let mut pools = PoolsById::new();
+            for pool_id in pool_ids {
+                pools.insert(pool_id.clone(), found(self.pool_keys(pool_id).await)?);
+            }
+            Ok(pools)
+        }
+    }
+
+    /// Info of every pool in `pool_ids`, one at a time unless the source
+    /// can batch them.
+    fn pool_info_by_ids(
+        &self,
+        pool_ids: &[String],
+    ) -> impl Future<Output = Result<PoolsById<PoolInfo>, Error>> + Send {
+        async move {
+            let mut pools = PoolsById::new();
+            for pool_id in pool_ids {
+                pools.insert(pool_id.clone(), found(self.pool_info(pool_id).await)?);
+            }
+            Ok(pools)
+        }
+    }
+}
+
+/// `None` for pools the source doesn't know, other errors unchanged.
+fn found<T>(result: Result<T, Error>) -> Result<Option<T>, Error> {
+    match result {
+        Ok(pool) => Ok(Some(pool)),
+        Err(Error::PoolNotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Pools of a batch lookup, failing with every id the source doesn't know.
+pub fn all_found<T>(pools: PoolsById<T>) -> Result<BTreeMap<String, T>, Error> {
+    let missing = pools
+        .iter()
+        .filter(|(_, pool)| pool.is_none())
+        .map(|(pool_id, _)| pool_id.as_str())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(Error::PoolNotFound(missing.join(", ")));
+    }
+
+    Ok(pools
+        .into_iter()
+        .filter_map(|(pool_id, pool)| Some((pool_id, pool?)))
+        .collect())
+}
+
+/// Single pool out of a batch lookup.
+fn single<T>(mut pools: PoolsById<T>, pool_id: &str) -> Result<T, Error> {
+    pools
+        .remove(pool_id)
+        .flatten()
+        .ok_or_else(|| Error::PoolNotFound(pool_id.to_string()))
+}
+
+/// Backend selected by the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolSourceKind {
+    /// Raydium HTTP API
+    Api,
+    /// Decode the pool accounts on chain, pool info from the API
+    #[default]
+    Rpc,
+    /// On-disk cache in front of the rpc source
+    Cache,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolSourceConfig {
+    pub kind: PoolSourceKind,
+    /// Base URL of the Raydium API
+    pub api_base_url: String,
+    /// Directory of the on-disk cache
+    pub cache_dir: PathBuf,
+    /// How long cached pool info stays fresh, pool keys never expire
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for PoolSourceConfig {
+    fn default() -> Self {
+        Self {
+            kind: PoolSourceKind::default(),
+            api_base_url: "https://api-v3.raydium.io".to_string(),
+            cache_dir: PathBuf::from(".cache/pools"),
+            cache_ttl_secs: 300,
+        }
+    }
+}
+
+/// Source picked by `config.pool_source.kind`.
+pub enum ConfiguredPoolSource {
+    Api(ApiPoolSource),
+    Rpc(RpcPoolSource),
+    Cache(CachePoolSource<RpcPoolSource>),
+}
+
+impl ConfiguredPoolSource {
+    /// `api` is shared with the rpc source, which takes pool info and lookup
+    /// tables from it.
+    pub fn new(config: &Config, client: Arc<Client>, api: ApiPoolSource) -> Self {
+        let rpc = RpcPoolSource::new(
+            client,
+            config.api_fallback.then_some(api.clone()),
+            api.clone(),
+        );
+
+        match config.pool_source.kind {
+            PoolSourceKind::Api => ConfiguredPoolSource::Api(api),
+            PoolSourceKind::Rpc => ConfiguredPoolSource::Rpc(rpc),
+            PoolSourceKind::Cache => ConfiguredPoolSource::Cache(CachePoolSource::new(
+                &config.pool_source.cache_dir,
+                std::time::Duration::from_secs(config.pool_source.cache_ttl_secs),
+                rpc,
+            )),
+        }
+    }
+}
+
+impl PoolSource for ConfiguredPoolSource {
+    async fn pool_keys(&self, pool_id: &str) -> Result<PoolKeys, Error> {
+        match self {
+            ConfiguredPoolSource::Api(source) => source.pool_keys(pool_id).await,
+            ConfiguredPoolSource::Rpc(source) => source.pool_keys(pool_id).await,
+            ConfiguredPoolSource::Cache(source) => source.pool_keys(pool_id).await,
+        }
+    }
+
+    async fn pool_info(&self, pool_id: &str) -> Result<PoolInfo, Error> {
+        match self {
+            ConfiguredPoolSource::Api(source) => source.pool_info(pool_id).await,
+            ConfiguredPoolSource::Rpc(source) => source.pool_info(pool_id).await,
+            ConfiguredPoolSource::Cache(source) => source.pool_info(pool_id).await,
+        }
+    }
+
+    async fn pool_keys_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolKeys>, Error> {
+        match self {
+            ConfiguredPoolSource::Api(source) => source.pool_keys_by_ids(pool_ids).await,
+            ConfiguredPoolSource::Rpc(source) => source.pool_keys_by_ids(pool_ids).await,
+            ConfiguredPoolSource::Cache(source) => source.pool_keys_by_ids(pool_ids).await,
+        }
+    }
+
+    async fn pool_info_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolInfo>, Error> {
+        match self {
+            ConfiguredPoolSource::Api(source) => source.pool_info_by_ids(pool_ids).await,
+            ConfiguredPoolSource::Rpc(source) => source.pool_info_by_ids(pool_ids).await,
+            ConfiguredPoolSource::Cache(source) => source.pool_info_by_ids(pool_ids).await,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{
+    commands::{
+        fetch_pool_info::PoolInfo,
+        fetch_pool_keys::{fetch_pool_keys_onchain_by_ids, PoolKeys},
+    },
+    error::Error,
+    pool_source::{found, single, ApiPoolSource, PoolSource, PoolsById},
+    state::market::fetch_market_keys,
+    utils::{client::Client, pubkey::pubkey_from_str},
+};
+
+/// Pool keys decoded from the AMM and market accounts on chain.
+///
+/// Pool info has no on-chain equivalent and always comes from the API.
+pub struct RpcPoolSource {
+    client: Arc<Client>,
+    /// Used for the keys when they can't be decoded on chain
+    fallback: Option<ApiPoolSource>,
+    api: ApiPoolSource,
+}
+
+impl RpcPoolSource {
+    pub fn new(client: Arc<Client>, fallback: Option<ApiPoolSource>, api: ApiPoolSource) -> Self {
+        Self {
+            client,
+            fallback,
+            api,
+        }
+    }
+}
+
+impl RpcPoolSource {
+    /// Keys of `pool_id` from the API fallback after `error` decoding them on
+    /// chain.
+    async fn fallback_pool_keys(&self, pool_id: &str, error: Error) -> Result<PoolKeys, Error> {
+        let Some(fallback) = &self.fallback else {
+            return Err(error);
+        };
+        warn!("Failed decoding pool keys on chain, using API: {}", error);
+        let mut pool_keys = fallback.pool_keys(pool_id).await?;
+
+        // Prefer the market accounts from chain when the market is readable
+        match fetch_market_keys(&self.client, &pubkey_from_str(&pool_keys.marketId)?).await {
+            Ok(market_keys) => pool_keys.set_market_keys(&market_keys),
+            Err(e) => warn!("Failed decoding market on chain, using API: {}", e),
+        }
+
+        Ok(pool_keys)
+    }
+
+    /// Fill in the lookup table of pools decoded on chain. The AMM account
+    /// doesn't record it, so it comes from the API fallback when enabled;
+    /// otherwise, or when the API can't be reached, only the config
+    /// `lookup_tables` are used.
+    async fn fill_lookup_tables(&self, pools: &mut PoolsById<PoolKeys>) {
+        let Some(fallback) = &self.fallback else {
+            return;
+        };
+        let pool_ids = pools
+            .iter()
+            .filter(|(_, pool_keys)| {
+                matches!(pool_keys, Some(pool_keys) if pool_keys.lookupTableAccount.is_empty())
+            })
+            .map(|(pool_id, _)| pool_id.clone())
+            .collect::<Vec<_>>();
+        if pool_ids.is_empty() {
+            return;
+        }
+
+        match fallback.pool_keys_by_ids(&pool_ids).await {
+            Ok(api_pools) => {
+                for (pool_id, api_keys) in api_pools {
+                    if let (Some(api_keys), Some(Some(pool_keys))) =
+                        (api_keys, pools.get_mut(&pool_id))
+                    {
+                        pool_keys.lookupTableAccount = api_keys.lookupTableAccount;
+                    }
+                }
+            }
+            Err(e) => warn!("Failed fetching pool lookup tables from the API: {}", e),
+        }
+    }
+}
+
+impl PoolSource for RpcPoolSource {
+    async fn pool_keys(&self, pool_id: &str) -> Result<PoolKeys, Error> {
+        single(
+            self.pool_keys_by_ids(&[pool_id.to_string()]).await?,
+            pool_id,
+        )
+    }
+
+    async fn pool_info(&self, pool_id: &str) -> Result<PoolInfo, Error> {
+        self.api.pool_info(pool_id).await
+    }
+
+    async fn pool_keys_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolKeys>, Error> {
+        let mut pools = PoolsById::new();
+        for (pool_id, pool_keys) in fetch_pool_keys_onchain_by_ids(&self.client, pool_ids).await? {
+            let pool_keys = match pool_keys {
+                Ok(pool_keys) => Some(pool_keys),
+                Err(e) => found(self.fallback_pool_keys(&pool_id, e).await)?,
+            };
+            pools.insert(pool_id, pool_keys);
+        }
+        self.fill_lookup_tables(&mut pools).await;
+
+        Ok(pools)
+    }
+
+    async fn pool_info_by_ids(&self, pool_ids: &[String]) -> Result<PoolsById<PoolInfo>, Error> {
+        self.api.pool_info_by_ids(pool_ids).await
+    }
+}
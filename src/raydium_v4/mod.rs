@@ -0,0 +1,496 @@
+//! Library entry point for Raydium AMM v4 liquidity.
+//!
+//! Quotes and transactions are built from raw amounts without prompting,
+//! logging above debug or sending anything; only `execute` touches the chain.
+
+use rust_decimal::{prelude::FromPrimitive, Decimal};
+use serde::Serialize;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair};
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::{
+    commands::fetch_pool_keys::{PoolKeys, TokenInfo},
+    config::Config,
+    error::Error,
+    instructions::{
+        add_liquidity::{
+            make_add_liquidity_instruction, FixedSide, IxUserKeys as AddIxUserKeys,
+            LiquidityAddInstructionParams,
+        },
+        remove_liquidity::{
+            make_remove_liquidity_instruction, IxUserKeys as RemoveIxUserKeys,
+            RemoveLiquidityInstructionParams,
+        },
+    },
+    math::{
+        deposit, max_amount_with_slippage, min_amount_with_slippage, withdraw, DepositQuote,
+        SwapDirection, WithdrawQuote,
+    },
+    pool_source::{ApiPoolSource, ConfiguredPoolSource, PoolSource},
+    state::pool_state::fetch_pool_state,
+    utils::{
+        client::{
+            get_client, Client, HandleTokenAccountParams, TokenAccountInfo, TokenSide,
+            TransactionResult, WatchedAccount, WsolOptions,
+        },
+        pubkey::pubkey_from_str,
+    },
+};
+
+mod round_trip;
+mod swap;
+mod zap;
+
+pub use round_trip::RoundTripQuote;
+pub use swap::{ExactSwapQuote, SwapAmount};
+pub use zap::{ZapInQuote, ZapOutQuote};
+
+/// Deposit of an exact amount on `fixed_side`, with the bounds sent to the
+/// program.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddQuote {
+    pub pool_id: String,
+    pub fixed_side: FixedSide,
+    pub slippage_percentage: f64,
+    /// Expected deposit at the current reserves
+    pub deposit: DepositQuote,
+    /// Most base the program may take
+    pub max_base_amount: u64,
+    /// Most quote the program may take
+    pub max_quote_amount: u64,
+    /// Least of the other side the program must take
+    pub other_amount_min: u64,
+}
+
+/// Burn of `lp_amount`, with the minimums sent to the program.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoveQuote {
+    pub pool_id: String,
+    pub lp_amount: u64,
+    pub slippage_percentage: f64,
+    /// Expected withdrawal at the current reserves
+    pub withdraw: WithdrawQuote,
+    pub base_amount_min: u64,
+    pub quote_amount_min: u64,
+}
+
+/// Unsigned instructions and everything needed to sign and report them.
+pub struct PreparedTransaction {
+    pub instructions: Vec<Instruction>,
+    /// Keypairs of temporary WSOL accounts, signing next to the payer
+    pub additional_signers: Vec<Keypair>,
+    pub lookup_tables: Vec<Pubkey>,
+    /// Accounts whose balances the transaction changes
+    pub watched: Vec<WatchedAccount>,
+}
+
+/// Raydium AMM v4 liquidity client for the configured wallet, reading pools
+/// from `S`.
+pub struct RaydiumV4Client<S = ConfiguredPoolSource> {
+    config: Config,
+    client: Arc<Client>,
+    api: ApiPoolSource,
+    pool_source: S,
+}
+
+impl RaydiumV4Client {
+    /// Client reading pools from the source selected by the config.
+    pub fn new(config: Config) -> Result<Self, Error> {
+        let client = Arc::new(get_client(&config)?);
+        let api = ApiPoolSource::new(&config.pool_source.api_base_url);
+        let pool_source = ConfiguredPoolSource::new(&config, client.clone(), api.clone());
+        Ok(Self {
+            config,
+            client,
+            api,
+            pool_source,
+        })
+    }
+}
+
+impl<S: PoolSource> RaydiumV4Client<S> {
+    /// The same client reading pools from `pool_source` instead.
+    pub fn with_pool_source<T: PoolSource>(self, pool_source: T) -> RaydiumV4Client<T> {
+        RaydiumV4Client {
+            config: self.config,
+            client: self.client,
+            api: self.api,
+            pool_source,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Raydium API, for the lookups only it can answer (by mint, by LP mint).
+    pub fn api(&self) -> &ApiPoolSource {
+        &self.api
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.client.get_payer_pubkey()
+    }
+
+    pub fn pool_source(&self) -> &S {
+        &self.pool_source
+    }
+
+    pub async fn pool_keys(&self, pool_id: &str) -> Result<PoolKeys, Error> {
+        self.pool_source.pool_keys(pool_id).await
+    }
+
+    /// Quote depositing exactly `amount` raw tokens on `fixed_side`.
+    pub async fn quote_add(
+        &self,
+        pool_keys: &PoolKeys,
+        fixed_side: FixedSide,
+        amount: u64,
+        slippage_percentage: f64,
+    ) -> Result<AddQuote, Error> {
+        // Refuse pools the program can't move tokens for before quoting anything
+        pool_keys.token_program()?;
+
+        let pool_state = fetch_pool_state(&self.client, pool_keys).await?;
+        debug!("Reserves: {:?}", pool_state.reserves);
+        debug!("LP Supply: {}", pool_state.lp_supply());
+
+        let quote = deposit(
+            &pool_state.reserves,
+            pool_state.lp_supply(),
+            fixed_side,
+            amount,
+        )?;
+        debug!("Deposit Quote: {:?}", quote);
+
+        bound_deposit(pool_keys, fixed_side, quote, slippage_percentage)
+    }
+
+    /// Instructions depositing `quote`, creating and wrapping the payer's
+    /// token accounts as needed.
+    pub async fn build_add(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &AddQuote,
+        wsol: WsolOptions,
+    ) -> Result<PreparedTransaction, Error> {
+        let (token_a_info, token_b_info, token_lp_info) = tokio::try_join!(
+            self.token_account(
+                TokenSide::In,
+                quote.max_base_amount,
+                pool_keys,
+                Side::Base,
+                wsol,
+            ),
+            self.token_account(
+                TokenSide::In,
+                quote.max_quote_amount,
+                pool_keys,
+                Side::Quote,
+                wsol,
+            ),
+            self.token_account(TokenSide::Out, 0, pool_keys, Side::Lp, wsol),
+        )?;
+
+        let add_liquidity_ix = self.add_instruction(
+            pool_keys,
+            quote,
+            token_a_info.token_account,
+            token_b_info.token_account,
+            token_lp_info.token_account,
+        )?;
+
+        prepare(
+            pool_keys,
+            vec![
+                (token_a_info, Side::Base),
+                (token_b_info, Side::Quote),
+                (token_lp_info, Side::Lp),
+            ],
+            vec![add_liquidity_ix],
+        )
+    }
+
+    /// Quote burning `lp_amount` raw LP tokens. Explicit minimums override the
+    /// ones derived from the slippage.
+    pub async fn quote_remove(
+        &self,
+        pool_keys: &PoolKeys,
+        lp_amount: u64,
+        slippage_percentage: f64,
+        base_amount_min: Option<u64>,
+        quote_amount_min: Option<u64>,
+    ) -> Result<RemoveQuote, Error> {
+        pool_keys.token_program()?;
+
+        let pool_state = fetch_pool_state(&self.client, pool_keys).await?;
+        debug!("Reserves: {:?}", pool_state.reserves);
+        debug!("LP Supply: {}", pool_state.lp_supply());
+
+        let quote = withdraw(&pool_state.reserves, pool_state.lp_supply(), lp_amount)?;
+        debug!("Withdraw Quote: {:?}", quote);
+
+        bound_withdraw(
+            pool_keys,
+            quote,
+            slippage_percentage,
+            base_amount_min,
+            quote_amount_min,
+        )
+    }
+
+    /// Instructions burning the LP of `quote`, creating and unwrapping the
+    /// payer's token accounts as needed.
+    pub async fn build_remove(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &RemoveQuote,
+        wsol: WsolOptions,
+    ) -> Result<PreparedTransaction, Error> {
+        let (token_lp_info, token_a_info, token_b_info) = tokio::try_join!(
+            self.token_account(TokenSide::In, quote.lp_amount, pool_keys, Side::Lp, wsol),
+            self.token_account(TokenSide::Out, 0, pool_keys, Side::Base, wsol),
+            self.token_account(TokenSide::Out, 0, pool_keys, Side::Quote, wsol),
+        )?;
+
+        let remove_liquidity_ix = self.remove_instruction(
+            pool_keys,
+            quote,
+            token_lp_info.token_account,
+            token_a_info.token_account,
+            token_b_info.token_account,
+        )?;
+
+        prepare(
+            pool_keys,
+            vec![
+                (token_lp_info, Side::Lp),
+                (token_a_info, Side::Base),
+                (token_b_info, Side::Quote),
+            ],
+            vec![remove_liquidity_ix],
+        )
+    }
+
+    /// Sign and send `transaction` with the payer, or only simulate it when
+    /// `dry_run` is set.
+    pub async fn execute(
+        &self,
+        transaction: &PreparedTransaction,
+        dry_run: bool,
+    ) -> Result<TransactionResult, Error> {
+        self.client
+            .execute_transaction(
+                &transaction.instructions,
+                &transaction.additional_signers,
+                &transaction.lookup_tables,
+                &transaction.watched,
+                dry_run,
+            )
+            .await
+    }
+
+    fn add_instruction(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &AddQuote,
+        base_token_account: Pubkey,
+        quote_token_account: Pubkey,
+        lp_token_account: Pubkey,
+    ) -> Result<Instruction, Error> {
+        make_add_liquidity_instruction(LiquidityAddInstructionParams {
+            rpc_pool_keys: pool_keys,
+            user_keys: &AddIxUserKeys {
+                base_token_account,
+                quote_token_account,
+                lp_token_account,
+                owner: self.payer(),
+            },
+            base_amount_in: quote.max_base_amount,
+            quote_amount_in: quote.max_quote_amount,
+            fixed_side: quote.fixed_side,
+            other_amount_min: quote.other_amount_min,
+        })
+    }
+
+    fn remove_instruction(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &RemoveQuote,
+        lp_token_account: Pubkey,
+        base_token_account: Pubkey,
+        quote_token_account: Pubkey,
+    ) -> Result<Instruction, Error> {
+        make_remove_liquidity_instruction(RemoveLiquidityInstructionParams {
+            rpc_pool_keys: pool_keys,
+            user_keys: &RemoveIxUserKeys {
+                lp_token_account,
+                base_token_account,
+                quote_token_account,
+                owner: self.payer(),
+            },
+            lp_amount: quote.lp_amount,
+            base_amount_min: quote.base_amount_min,
+            quote_amount_min: quote.quote_amount_min,
+        })
+    }
+
+    async fn token_account(
+        &self,
+        side: TokenSide,
+        amount: u64,
+        pool_keys: &PoolKeys,
+        mint: Side,
+        wsol: WsolOptions,
+    ) -> Result<TokenAccountInfo, Error> {
+        let token = mint.token(pool_keys);
+        self.client
+            .handle_token_account(HandleTokenAccountParams {
+                side,
+                amount,
+                mint: pubkey_from_str(&token.address)?,
+                token_program: token.program_id()?,
+                token_account: None,
+                bypass_associated_check: false,
+                skip_close_account: wsol.keep,
+                check_create_ata_owner: true,
+                wsol_mode: wsol.mode,
+            })
+            .await
+    }
+}
+
+/// Pool token a user account holds.
+#[derive(Clone, Copy)]
+enum Side {
+    Base,
+    Quote,
+    Lp,
+}
+
+impl Side {
+    fn token(self, pool_keys: &PoolKeys) -> &TokenInfo {
+        match self {
+            Side::Base => &pool_keys.mintA,
+            Side::Quote => &pool_keys.mintB,
+            Side::Lp => &pool_keys.mintLp,
+        }
+    }
+
+    /// Sides sold and bought by a swap in `direction`.
+    fn swapped(direction: SwapDirection) -> (Side, Side) {
+        match direction {
+            SwapDirection::BaseToQuote => (Side::Base, Side::Quote),
+            SwapDirection::QuoteToBase => (Side::Quote, Side::Base),
+        }
+    }
+}
+
+/// Wrap `pool_instructions` between the setup and cleanup of the token
+/// accounts they share.
+fn prepare(
+    pool_keys: &PoolKeys,
+    token_accounts: Vec<(TokenAccountInfo, Side)>,
+    pool_instructions: Vec<Instruction>,
+) -> Result<PreparedTransaction, Error> {
+    let mut instructions = vec![];
+    let mut additional_signers = vec![];
+    let mut watched = vec![];
+    let mut end_instructions = vec![];
+
+    for (info, side) in token_accounts {
+        let token = side.token(pool_keys);
+        instructions.extend(info.start_instructions);
+        end_instructions.extend(info.end_instructions);
+        additional_signers.extend(info.additional_signers);
+        watched.push(WatchedAccount {
+            label: token.label().to_string(),
+            address: info.token_account,
+            decimals: token.decimals,
+        });
+    }
+    instructions.extend(pool_instructions);
+    instructions.extend(end_instructions);
+
+    Ok(PreparedTransaction {
+        instructions,
+        additional_signers,
+        lookup_tables: pool_keys.lookup_tables()?,
+        watched,
+    })
+}
+
+/// Bound `quote` for the program: the fixed side is taken exactly, the other
+/// side within the slippage.
+///
+/// The PnL the program books before the deposit shrinks both reserves at the
+/// same ratio, which can move the rounded-up other side by a raw unit, so the
+/// bounds on it are widened by one.
+fn bound_deposit(
+    pool_keys: &PoolKeys,
+    fixed_side: FixedSide,
+    quote: DepositQuote,
+    slippage_percentage: f64,
+) -> Result<AddQuote, Error> {
+    let slippage = slippage_from_percentage(slippage_percentage)?;
+    let other_amount = match fixed_side {
+        FixedSide::Base => quote.quote_amount,
+        FixedSide::Quote => quote.base_amount,
+    };
+    let max_other_amount = max_amount_with_slippage(other_amount, slippage)?.saturating_add(1);
+
+    let (max_base_amount, max_quote_amount) = match fixed_side {
+        FixedSide::Base => (quote.base_amount, max_other_amount),
+        FixedSide::Quote => (max_other_amount, quote.quote_amount),
+    };
+
+    Ok(AddQuote {
+        pool_id: pool_keys.id.clone(),
+        fixed_side,
+        slippage_percentage,
+        deposit: quote,
+        max_base_amount,
+        max_quote_amount,
+        other_amount_min: min_amount_with_slippage(other_amount, slippage)?.saturating_sub(1),
+    })
+}
+
+/// Bound `quote` for the program, lowering both sides by the slippage unless
+/// a minimum is given. The PnL the program books before the withdrawal comes
+/// out of what it returns, so the slippage has to cover it.
+fn bound_withdraw(
+    pool_keys: &PoolKeys,
+    quote: WithdrawQuote,
+    slippage_percentage: f64,
+    base_amount_min: Option<u64>,
+    quote_amount_min: Option<u64>,
+) -> Result<RemoveQuote, Error> {
+    let slippage = slippage_from_percentage(slippage_percentage)?;
+    let base_amount_min = match base_amount_min {
+        Some(amount) => amount,
+        None => min_amount_with_slippage(quote.base_amount, slippage)?,
+    };
+    let quote_amount_min = match quote_amount_min {
+        Some(amount) => amount,
+        None => min_amount_with_slippage(quote.quote_amount, slippage)?,
+    };
+
+    Ok(RemoveQuote {
+        pool_id: pool_keys.id.clone(),
+        lp_amount: quote.lp_amount,
+        slippage_percentage,
+        withdraw: quote,
+        base_amount_min,
+        quote_amount_min,
+    })
+}
+
+fn slippage_from_percentage(slippage_percentage: f64) -> Result<Decimal, Error> {
+    Decimal::from_f64(slippage_percentage)
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid slippage: {}", slippage_percentage)))
+}
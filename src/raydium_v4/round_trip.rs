@@ -0,0 +1,131 @@
+use serde::Serialize;
+use tracing::debug;
+
+use super::{
+    bound_deposit, bound_withdraw, prepare, AddQuote, PreparedTransaction, RaydiumV4Client,
+    RemoveQuote, Side,
+};
+use crate::{
+    commands::fetch_pool_keys::PoolKeys,
+    error::Error,
+    instructions::add_liquidity::FixedSide,
+    math::{deposit, deposit_lp_min, withdraw},
+    pool_source::PoolSource,
+    state::pool_state::fetch_pool_state,
+    utils::client::{TokenSide, WsolOptions},
+};
+
+/// Deposit and the withdrawal of the least LP it can mint.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundTripQuote {
+    pub add: AddQuote,
+    pub remove: RemoveQuote,
+}
+
+impl<S: PoolSource> RaydiumV4Client<S> {
+    /// Quote depositing exactly `amount` raw tokens on `fixed_side` and
+    /// burning the least LP the deposit can mint.
+    pub async fn quote_round_trip(
+        &self,
+        pool_keys: &PoolKeys,
+        fixed_side: FixedSide,
+        amount: u64,
+        slippage_percentage: f64,
+    ) -> Result<RoundTripQuote, Error> {
+        // Refuse pools the program can't move tokens for before quoting anything
+        pool_keys.token_program()?;
+
+        let pool_state = fetch_pool_state(&self.client, pool_keys).await?;
+        debug!("Reserves: {:?}", pool_state.reserves);
+        debug!("LP Supply: {}", pool_state.lp_supply());
+
+        let deposit_quote = deposit(
+            &pool_state.reserves,
+            pool_state.lp_supply(),
+            fixed_side,
+            amount,
+        )?;
+        debug!("Deposit Quote: {:?}", deposit_quote);
+
+        // The deposit mints less LP than quoted if the price moves before it
+        // lands, so only the least LP its bound on the other side allows is
+        // burned
+        let add = bound_deposit(pool_keys, fixed_side, deposit_quote, slippage_percentage)?;
+        let lp_amount_min = deposit_lp_min(
+            &pool_state.reserves,
+            pool_state.lp_supply(),
+            pool_state.swap_fee(),
+            fixed_side,
+            amount,
+            add.other_amount_min,
+        )?;
+        debug!("LP Amount Min: {}", lp_amount_min);
+
+        // The withdrawal is priced against the pool as it stands after the deposit
+        let withdraw_quote = withdraw(
+            &pool_state.reserves.after_deposit(&deposit_quote)?,
+            pool_state.lp_supply() + deposit_quote.lp_amount,
+            lp_amount_min,
+        )?;
+        debug!("Withdraw Quote: {:?}", withdraw_quote);
+
+        Ok(RoundTripQuote {
+            add,
+            remove: bound_withdraw(pool_keys, withdraw_quote, slippage_percentage, None, None)?,
+        })
+    }
+
+    /// Instructions depositing and withdrawing `quote` in a single
+    /// transaction.
+    pub async fn build_round_trip(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &RoundTripQuote,
+        wsol: WsolOptions,
+    ) -> Result<PreparedTransaction, Error> {
+        // Both instructions share the same token accounts, so WSOL is wrapped once
+        // for the deposit and unwrapped once after the withdrawal
+        let (token_a_info, token_b_info, token_lp_info) = tokio::try_join!(
+            self.token_account(
+                TokenSide::In,
+                quote.add.max_base_amount,
+                pool_keys,
+                Side::Base,
+                wsol,
+            ),
+            self.token_account(
+                TokenSide::In,
+                quote.add.max_quote_amount,
+                pool_keys,
+                Side::Quote,
+                wsol,
+            ),
+            self.token_account(TokenSide::Out, 0, pool_keys, Side::Lp, wsol),
+        )?;
+
+        let add_liquidity_ix = self.add_instruction(
+            pool_keys,
+            &quote.add,
+            token_a_info.token_account,
+            token_b_info.token_account,
+            token_lp_info.token_account,
+        )?;
+        let remove_liquidity_ix = self.remove_instruction(
+            pool_keys,
+            &quote.remove,
+            token_lp_info.token_account,
+            token_a_info.token_account,
+            token_b_info.token_account,
+        )?;
+
+        prepare(
+            pool_keys,
+            vec![
+                (token_a_info, Side::Base),
+                (token_b_info, Side::Quote),
+                (token_lp_info, Side::Lp),
+            ],
+            vec![add_liquidity_ix, remove_liquidity_ix],
+        )
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Serialize;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use tracing::debug;
+
+use super::{prepare, slippage_from_percentage, PreparedTransaction, RaydiumV4Client, Side};
+use crate::{
+    commands::fetch_pool_keys::PoolKeys,
+    error::Error,
+    instructions::swap::{make_swap_instruction, IxUserKeys, SwapAmounts, SwapInstructionParams},
+    math::{
+        max_amount_with_slippage, min_amount_with_slippage, swap_base_in, swap_base_out,
+        PoolReserves, SwapDirection, SwapFee, SwapQuote,
+    },
+    pool_source::PoolSource,
+    state::pool_state::fetch_pool_state,
+    utils::client::{TokenSide, WsolOptions},
+};
+
+/// Side of a swap given exactly, in raw units.
+#[derive(Clone, Copy, Debug)]
+pub enum SwapAmount {
+    ExactIn(u64),
+    ExactOut(u64),
+}
+
+/// Swap of an exact amount in or out, with the bounds sent to the program.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExactSwapQuote {
+    pub pool_id: String,
+    pub direction: SwapDirection,
+    pub slippage_percentage: f64,
+    /// Expected swap at the current reserves
+    pub swap: SwapQuote,
+    pub amounts: SwapAmounts,
+}
+
+impl ExactSwapQuote {
+    /// Most of the input side the program may take.
+    pub fn max_amount_in(&self) -> u64 {
+        match self.amounts {
+            SwapAmounts::BaseIn { amount_in, .. } => amount_in,
+            SwapAmounts::BaseOut { max_amount_in, .. } => max_amount_in,
+        }
+    }
+
+    /// Least of the output side the program must give.
+    pub fn min_amount_out(&self) -> u64 {
+        match self.amounts {
+            SwapAmounts::BaseIn {
+                minimum_amount_out, ..
+            } => minimum_amount_out,
+            SwapAmounts::BaseOut { amount_out, .. } => amount_out,
+        }
+    }
+}
+
+impl<S: PoolSource> RaydiumV4Client<S> {
+    /// Quote a swap in `direction` of exactly `amount` raw tokens in or out.
+    pub async fn quote_swap(
+        &self,
+        pool_keys: &PoolKeys,
+        direction: SwapDirection,
+        amount: SwapAmount,
+        slippage_percentage: f64,
+    ) -> Result<ExactSwapQuote, Error> {
+        // Refuse pools the program can't move tokens for before quoting anything
+        pool_keys.token_program()?;
+
+        let pool_state = fetch_pool_state(&self.client, pool_keys).await?;
+        debug!("Reserves: {:?}", pool_state.reserves);
+        debug!("Swap Fee: {:?}", pool_state.swap_fee());
+
+        quote_exact_swap(
+            pool_keys,
+            &pool_state.reserves,
+            pool_state.swap_fee(),
+            direction,
+            amount,
+            slippage_percentage,
+        )
+    }
+
+    /// Instructions swapping `quote`, creating and wrapping the payer's token
+    /// accounts as needed.
+    pub async fn build_swap(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &ExactSwapQuote,
+        wsol: WsolOptions,
+    ) -> Result<PreparedTransaction, Error> {
+        let (side_in, side_out) = Side::swapped(quote.direction);
+        let (token_in_info, token_out_info) = tokio::try_join!(
+            self.token_account(
+                TokenSide::In,
+                quote.max_amount_in(),
+                pool_keys,
+                side_in,
+                wsol,
+            ),
+            self.token_account(TokenSide::Out, 0, pool_keys, side_out, wsol),
+        )?;
+
+        let swap_ix = self.swap_instruction(
+            pool_keys,
+            quote,
+            token_in_info.token_account,
+            token_out_info.token_account,
+        )?;
+
+        prepare(
+            pool_keys,
+            vec![(token_in_info, side_in), (token_out_info, side_out)],
+            vec![swap_ix],
+        )
+    }
+
+    pub(super) fn swap_instruction(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &ExactSwapQuote,
+        source_token_account: Pubkey,
+        destination_token_account: Pubkey,
+    ) -> Result<Instruction, Error> {
+        make_swap_instruction(SwapInstructionParams {
+            rpc_pool_keys: pool_keys,
+            user_keys: &IxUserKeys {
+                source_token_account,
+                destination_token_account,
+                owner: self.payer(),
+            },
+            amounts: quote.amounts,
+        })
+    }
+}
+
+/// Quote the exact side of a swap against `reserves` and bound the other side
+/// by the slippage.
+pub(super) fn quote_exact_swap(
+    pool_keys: &PoolKeys,
+    reserves: &PoolReserves,
+    fee: SwapFee,
+    direction: SwapDirection,
+    amount: SwapAmount,
+    slippage_percentage: f64,
+) -> Result<ExactSwapQuote, Error> {
+    let slippage = slippage_from_percentage(slippage_percentage)?;
+    let (swap, amounts) = match amount {
+        SwapAmount::ExactIn(amount_in) => {
+            let swap = swap_base_in(reserves, fee, direction, amount_in)?;
+            let amounts = SwapAmounts::BaseIn {
+                amount_in: swap.amount_in,
+                minimum_amount_out: min_amount_with_slippage(swap.amount_out, slippage)?,
+            };
+            (swap, amounts)
+        }
+        SwapAmount::ExactOut(amount_out) => {
+            let swap = swap_base_out(reserves, fee, direction, amount_out)?;
+            let amounts = SwapAmounts::BaseOut {
+                max_amount_in: max_amount_with_slippage(swap.amount_in, slippage)?,
+                amount_out: swap.amount_out,
+            };
+            (swap, amounts)
+        }
+    };
+    debug!("Swap Quote: {:?}", swap);
+
+    Ok(ExactSwapQuote {
+        pool_id: pool_keys.id.clone(),
+        direction,
+        slippage_percentage,
+        swap,
+        amounts,
+    })
+}
@@ -0,0 +1,255 @@
+use serde::Serialize;
+use tracing::debug;
+
+use super::{
+    bound_deposit, bound_withdraw, prepare, slippage_from_percentage,
+    swap::{quote_exact_swap, ExactSwapQuote, SwapAmount},
+    AddQuote, PreparedTransaction, RaydiumV4Client, RemoveQuote, Side,
+};
+use crate::{
+    commands::fetch_pool_keys::PoolKeys,
+    error::Error,
+    instructions::add_liquidity::FixedSide,
+    math::{withdraw, zap_in, SwapDirection},
+    pool_source::PoolSource,
+    state::pool_state::fetch_pool_state,
+    utils::client::{TokenSide, WsolOptions, USDC_MINT, WSOL_MINT},
+};
+
+/// Mints positions may be settled in.
+const SETTLEMENT_MINTS: [&str; 2] = [USDC_MINT, WSOL_MINT];
+
+/// Swap of part of a single-sided amount and the deposit of both sides.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZapInQuote {
+    /// Whole single-sided amount, swapped and deposited
+    pub amount_in: u64,
+    pub swap: ExactSwapQuote,
+    pub add: AddQuote,
+    /// Input tokens expected to be left over after the deposit
+    pub leftover_in: u64,
+    /// Swapped tokens expected to be left over after the deposit
+    pub leftover_out: u64,
+}
+
+/// Withdrawal and the swap of the unwanted side.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZapOutQuote {
+    pub remove: RemoveQuote,
+    /// Sale of the part of the unwanted side the withdrawal guarantees
+    pub swap: ExactSwapQuote,
+    /// Unwanted tokens expected to be left unsold, the share of the withdrawal
+    /// the slippage doesn't guarantee
+    pub leftover_in: u64,
+}
+
+impl<S: PoolSource> RaydiumV4Client<S> {
+    /// Quote zapping `amount` raw tokens of the input side of `direction` into
+    /// the pool.
+    pub async fn quote_zap_in(
+        &self,
+        pool_keys: &PoolKeys,
+        direction: SwapDirection,
+        amount: u64,
+        slippage_percentage: f64,
+    ) -> Result<ZapInQuote, Error> {
+        // Refuse pools the program can't move tokens for before quoting anything
+        pool_keys.token_program()?;
+
+        let pool_state = fetch_pool_state(&self.client, pool_keys).await?;
+        debug!("Reserves: {:?}", pool_state.reserves);
+        debug!("LP Supply: {}", pool_state.lp_supply());
+
+        let slippage = slippage_from_percentage(slippage_percentage)?;
+        let split = zap_in(
+            &pool_state.reserves,
+            pool_state.lp_supply(),
+            pool_state.swap_fee(),
+            direction,
+            amount,
+            slippage,
+        )?;
+        debug!("Zap In Split: {:?}", split);
+
+        let swap = quote_exact_swap(
+            pool_keys,
+            &pool_state.reserves,
+            pool_state.swap_fee(),
+            direction,
+            SwapAmount::ExactIn(split.swap.amount_in),
+            slippage_percentage,
+        )?;
+
+        // The input side can't take more than what is left of it after the swap
+        let remaining = amount - split.swap.amount_in;
+        let mut add = bound_deposit(
+            pool_keys,
+            split.fixed_side,
+            split.deposit,
+            slippage_percentage,
+        )?;
+        match split.fixed_side {
+            FixedSide::Base => add.max_quote_amount = add.max_quote_amount.min(remaining),
+            FixedSide::Quote => add.max_base_amount = add.max_base_amount.min(remaining),
+        }
+
+        Ok(ZapInQuote {
+            amount_in: amount,
+            swap,
+            add,
+            leftover_in: split.leftover_in,
+            leftover_out: split.leftover_out,
+        })
+    }
+
+    /// Instructions swapping and depositing `quote` in a single transaction.
+    pub async fn build_zap_in(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &ZapInQuote,
+        wsol: WsolOptions,
+    ) -> Result<PreparedTransaction, Error> {
+        // The swap output lands in the account the deposit draws from, so WSOL is
+        // wrapped once for the whole amount and leftovers are unwrapped at the end
+        let (side_in, side_out) = Side::swapped(quote.swap.direction);
+        let (token_in_info, token_out_info, token_lp_info) = tokio::try_join!(
+            self.token_account(TokenSide::In, quote.amount_in, pool_keys, side_in, wsol),
+            self.token_account(TokenSide::Out, 0, pool_keys, side_out, wsol),
+            self.token_account(TokenSide::Out, 0, pool_keys, Side::Lp, wsol),
+        )?;
+
+        let swap_ix = self.swap_instruction(
+            pool_keys,
+            &quote.swap,
+            token_in_info.token_account,
+            token_out_info.token_account,
+        )?;
+        let (base_token_account, quote_token_account) = match quote.swap.direction {
+            SwapDirection::BaseToQuote => {
+                (token_in_info.token_account, token_out_info.token_account)
+            }
+            SwapDirection::QuoteToBase => {
+                (token_out_info.token_account, token_in_info.token_account)
+            }
+        };
+        let add_liquidity_ix = self.add_instruction(
+            pool_keys,
+            &quote.add,
+            base_token_account,
+            quote_token_account,
+            token_lp_info.token_account,
+        )?;
+
+        prepare(
+            pool_keys,
+            vec![
+                (token_in_info, side_in),
+                (token_out_info, side_out),
+                (token_lp_info, Side::Lp),
+            ],
+            vec![swap_ix, add_liquidity_ix],
+        )
+    }
+
+    /// Quote burning `lp_amount` raw LP tokens and selling the input side of
+    /// `direction`, settling in its output side.
+    pub async fn quote_zap_out(
+        &self,
+        pool_keys: &PoolKeys,
+        lp_amount: u64,
+        direction: SwapDirection,
+        slippage_percentage: f64,
+    ) -> Result<ZapOutQuote, Error> {
+        let (_, side_out) = Side::swapped(direction);
+        let target_mint = &side_out.token(pool_keys).address;
+        if !SETTLEMENT_MINTS.contains(&target_mint.as_str()) {
+            return Err(Error::InvalidInput(format!(
+                "Positions can only be settled in USDC or SOL, got {}",
+                target_mint
+            )));
+        }
+
+        // Refuse pools the program can't move tokens for before quoting anything
+        pool_keys.token_program()?;
+
+        let pool_state = fetch_pool_state(&self.client, pool_keys).await?;
+        debug!("Reserves: {:?}", pool_state.reserves);
+        debug!("LP Supply: {}", pool_state.lp_supply());
+
+        let withdraw_quote = withdraw(&pool_state.reserves, pool_state.lp_supply(), lp_amount)?;
+        debug!("Withdraw Quote: {:?}", withdraw_quote);
+
+        let remove = bound_withdraw(pool_keys, withdraw_quote, slippage_percentage, None, None)?;
+
+        // Only the part of the unwanted side the withdrawal is sure to return is
+        // sold, so price drift within the slippage can't fail the swap. The rest
+        // of the side stays in the wallet. The swap is priced against the pool as
+        // it stands after the expected withdrawal.
+        let (swap_amount_in, sold_amount) = match direction {
+            SwapDirection::BaseToQuote => (remove.base_amount_min, withdraw_quote.base_amount),
+            SwapDirection::QuoteToBase => (remove.quote_amount_min, withdraw_quote.quote_amount),
+        };
+        let swap = quote_exact_swap(
+            pool_keys,
+            &pool_state.reserves.after_withdraw(&withdraw_quote)?,
+            pool_state.swap_fee(),
+            direction,
+            SwapAmount::ExactIn(swap_amount_in),
+            slippage_percentage,
+        )?;
+
+        Ok(ZapOutQuote {
+            remove,
+            swap,
+            leftover_in: sold_amount - swap_amount_in,
+        })
+    }
+
+    /// Instructions withdrawing and swapping `quote` in a single transaction.
+    pub async fn build_zap_out(
+        &self,
+        pool_keys: &PoolKeys,
+        quote: &ZapOutQuote,
+        wsol: WsolOptions,
+    ) -> Result<PreparedTransaction, Error> {
+        let (token_lp_info, token_a_info, token_b_info) = tokio::try_join!(
+            self.token_account(
+                TokenSide::In,
+                quote.remove.lp_amount,
+                pool_keys,
+                Side::Lp,
+                wsol,
+            ),
+            self.token_account(TokenSide::Out, 0, pool_keys, Side::Base, wsol),
+            self.token_account(TokenSide::Out, 0, pool_keys, Side::Quote, wsol),
+        )?;
+
+        let remove_liquidity_ix = self.remove_instruction(
+            pool_keys,
+            &quote.remove,
+            token_lp_info.token_account,
+            token_a_info.token_account,
+            token_b_info.token_account,
+        )?;
+        let (source_token_account, destination_token_account) = match quote.swap.direction {
+            SwapDirection::BaseToQuote => (token_a_info.token_account, token_b_info.token_account),
+            SwapDirection::QuoteToBase => (token_b_info.token_account, token_a_info.token_account),
+        };
+        let swap_ix = self.swap_instruction(
+            pool_keys,
+            &quote.swap,
+            source_token_account,
+            destination_token_account,
+        )?;
+
+        prepare(
+            pool_keys,
+            vec![
+                (token_lp_info, Side::Lp),
+                (token_a_info, Side::Base),
+                (token_b_info, Side::Quote),
+            ],
+            vec![remove_liquidity_ix, swap_ix],
+        )
+    }
+}
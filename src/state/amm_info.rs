@@ -0,0 +1,125 @@
+use bytemuck::{Pod, Zeroable};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::Error;
+
+/// Raydium AMM v4 program.
+pub const AMM_V4_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+
+/// Seed of the AMM authority PDA, combined with `AmmInfo::nonce`.
+pub const AMM_AUTHORITY_SEED: &[u8] = b"amm authority";
+
+/// Pool status values with order book permission (`Initialized`,
+/// `OrderBookOnly`).
+const ORDERBOOK_STATUSES: [u64; 2] = [1, 5];
+
+/// Raydium AMM v4 fee parameters, stored as numerator/denominator pairs.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Fees {
+    pub min_separate_numerator: u64,
+    pub min_separate_denominator: u64,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub pnl_numerator: u64,
+    pub pnl_denominator: u64,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+}
+
+/// Raydium AMM v4 accounting data.
+///
+/// The program stores the swap counters as `u128`, which are kept here as
+/// little-endian `[u64; 2]` pairs so the struct has no alignment padding.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct StateData {
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+    pub total_pnl_pc: u64,
+    pub total_pnl_coin: u64,
+    pub pool_open_time: u64,
+    pub padding: [u64; 2],
+    pub orderbook_to_init_time: u64,
+    pub swap_coin_in_amount: [u64; 2],
+    pub swap_pc_out_amount: [u64; 2],
+    pub swap_acc_pc_fee: u64,
+    pub swap_pc_in_amount: [u64; 2],
+    pub swap_coin_out_amount: [u64; 2],
+    pub swap_acc_coin_fee: u64,
+}
+
+/// On-chain layout of a Raydium AMM v4 pool account (752 bytes).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct AmmInfo {
+    pub status: u64,
+    pub nonce: u64,
+    pub order_num: u64,
+    pub depth: u64,
+    pub coin_decimals: u64,
+    pub pc_decimals: u64,
+    pub state: u64,
+    pub reset_flag: u64,
+    pub min_size: u64,
+    pub vol_max_cut_ratio: u64,
+    pub amount_wave: u64,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub min_price_multiplier: u64,
+    pub max_price_multiplier: u64,
+    pub sys_decimal_value: u64,
+    pub fees: Fees,
+    pub state_data: StateData,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_vault_mint: Pubkey,
+    pub pc_vault_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub open_orders: Pubkey,
+    pub market: Pubkey,
+    pub market_program: Pubkey,
+    pub target_orders: Pubkey,
+    pub padding1: [u64; 8],
+    pub amm_owner: Pubkey,
+    pub lp_amount: u64,
+    pub client_order_id: u64,
+    pub recent_epoch: u64,
+    pub padding2: u64,
+}
+
+impl AmmInfo {
+    pub const LEN: usize = std::mem::size_of::<AmmInfo>();
+
+    /// Offset of `coin_vault_mint`, for `memcmp` filters.
+    pub const COIN_MINT_OFFSET: usize = std::mem::offset_of!(AmmInfo, coin_vault_mint);
+
+    /// Offset of `pc_vault_mint`, for `memcmp` filters.
+    pub const PC_MINT_OFFSET: usize = std::mem::offset_of!(AmmInfo, pc_vault_mint);
+
+    /// Decode an `AmmInfo` from raw account data.
+    pub fn from_account_data(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != Self::LEN {
+            return Err(Error::AccountDecode(format!(
+                "AmmInfo: expected {} bytes, got {}",
+                Self::LEN,
+                data.len()
+            )));
+        }
+
+        bytemuck::try_pod_read_unaligned(data)
+            .map_err(|e| Error::AccountDecode(format!("AmmInfo: {}", e)))
+    }
+
+    /// Whether the program counts the open orders balances in the reserves.
+    pub fn orderbook_enabled(&self) -> bool {
+        ORDERBOOK_STATUSES.contains(&self.status)
+    }
+
+    /// Derive the AMM authority from the nonce stored in the pool.
+    pub fn authority(&self, program_id: &Pubkey) -> Result<Pubkey, Error> {
+        Pubkey::create_program_address(&[AMM_AUTHORITY_SEED, &[self.nonce as u8]], program_id)
+            .map_err(|e| Error::AccountDecode(format!("AMM authority: {}", e)))
+    }
+}
@@ -0,0 +1,123 @@
+use bytemuck::{Pod, Zeroable};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{error::Error, utils::client::Client};
+
+/// Length of the `"serum"` padding that precedes every market account.
+const ACCOUNT_HEAD_PADDING: usize = 5;
+/// Length of the `"padding"` trailer that follows every market account.
+const ACCOUNT_TAIL_PADDING: usize = 7;
+
+/// Market accounts passed to the AMM instructions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketKeys {
+    pub program_id: Pubkey,
+    pub id: Pubkey,
+    pub authority: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+}
+
+/// On-chain layout of an OpenBook/Serum `MarketState`, without the head and
+/// tail padding.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct MarketState {
+    pub account_flags: u64,
+    pub own_address: Pubkey,
+    pub vault_signer_nonce: u64,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub coin_deposits_total: u64,
+    pub coin_fees_accrued: u64,
+    pub pc_vault: Pubkey,
+    pub pc_deposits_total: u64,
+    pub pc_fees_accrued: u64,
+    pub pc_dust_threshold: u64,
+    pub req_q: Pubkey,
+    pub event_q: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub fee_rate_bps: u64,
+    pub referrer_rebates_accrued: u64,
+}
+
+impl MarketState {
+    pub const LEN: usize = std::mem::size_of::<MarketState>();
+
+    /// Decode a `MarketState` from raw account data.
+    ///
+    /// Permissioned (v2) markets append extra authorities after the base
+    /// layout, so only the leading bytes are decoded.
+    pub fn from_account_data(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < ACCOUNT_HEAD_PADDING + Self::LEN + ACCOUNT_TAIL_PADDING {
+            return Err(Error::AccountDecode(format!(
+                "MarketState: expected at least {} bytes, got {}",
+                ACCOUNT_HEAD_PADDING + Self::LEN + ACCOUNT_TAIL_PADDING,
+                data.len()
+            )));
+        }
+
+        bytemuck::try_pod_read_unaligned(
+            &data[ACCOUNT_HEAD_PADDING..ACCOUNT_HEAD_PADDING + Self::LEN],
+        )
+        .map_err(|e| Error::AccountDecode(format!("MarketState: {}", e)))
+    }
+
+    /// Derive the market vault signer from the nonce stored in the market.
+    pub fn vault_signer(&self, market_program_id: &Pubkey) -> Result<Pubkey, Error> {
+        Pubkey::create_program_address(
+            &[
+                self.own_address.as_ref(),
+                &self.vault_signer_nonce.to_le_bytes(),
+            ],
+            market_program_id,
+        )
+        .map_err(|e| Error::AccountDecode(format!("Market vault signer: {}", e)))
+    }
+
+    /// Collect the accounts the AMM instructions need from this market.
+    pub fn keys(&self, market_program_id: &Pubkey) -> Result<MarketKeys, Error> {
+        Ok(MarketKeys {
+            program_id: *market_program_id,
+            id: self.own_address,
+            authority: self.vault_signer(market_program_id)?,
+            base_vault: self.coin_vault,
+            quote_vault: self.pc_vault,
+            bids: self.bids,
+            asks: self.asks,
+            event_queue: self.event_q,
+        })
+    }
+}
+
+/// Decode the market account at `market_id` and collect its keys, refusing
+/// an account that holds another market.
+pub fn market_keys_from_account(
+    market_id: &Pubkey,
+    data: &[u8],
+    market_program_id: &Pubkey,
+) -> Result<MarketKeys, Error> {
+    let market_state = MarketState::from_account_data(data)?;
+
+    if &market_state.own_address != market_id {
+        return Err(Error::AccountDecode(format!(
+            "MarketState: own address {} does not match {}",
+            market_state.own_address, market_id
+        )));
+    }
+
+    market_state.keys(market_program_id)
+}
+
+/// Fetch and decode a market account, using its owner as the market program.
+pub async fn fetch_market_keys(client: &Client, market_id: &Pubkey) -> Result<MarketKeys, Error> {
+    let account = client.get_account(market_id).await?;
+    market_keys_from_account(market_id, &account.data, &account.owner)
+}
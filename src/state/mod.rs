@@ -0,0 +1,4 @@
+pub mod amm_info;
+pub mod market;
+pub mod open_orders;
+pub mod pool_state;
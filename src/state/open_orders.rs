@@ -0,0 +1,43 @@
+use bytemuck::{Pod, Zeroable};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::Error;
+
+/// Length of the `"serum"` padding that precedes every open orders account.
+const ACCOUNT_HEAD_PADDING: usize = 5;
+
+/// Leading fields of an OpenBook/Serum `OpenOrders` account.
+///
+/// Only the balances are decoded; the order slots that follow are not needed
+/// to value the AMM's position on the order book.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct OpenOrders {
+    pub account_flags: u64,
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub native_coin_free: u64,
+    pub native_coin_total: u64,
+    pub native_pc_free: u64,
+    pub native_pc_total: u64,
+}
+
+impl OpenOrders {
+    pub const LEN: usize = std::mem::size_of::<OpenOrders>();
+
+    /// Decode the leading `OpenOrders` fields from raw account data.
+    pub fn from_account_data(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < ACCOUNT_HEAD_PADDING + Self::LEN {
+            return Err(Error::AccountDecode(format!(
+                "OpenOrders: expected at least {} bytes, got {}",
+                ACCOUNT_HEAD_PADDING + Self::LEN,
+                data.len()
+            )));
+        }
+
+        bytemuck::try_pod_read_unaligned(
+            &data[ACCOUNT_HEAD_PADDING..ACCOUNT_HEAD_PADDING + Self::LEN],
+        )
+        .map_err(|e| Error::AccountDecode(format!("OpenOrders: {}", e)))
+    }
+}
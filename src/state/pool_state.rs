@@ -0,0 +1,69 @@
+use solana_sdk::program_pack::Pack;
+use spl_token::state::Account as TokenAccount;
+
+use crate::{
+    commands::fetch_pool_keys::PoolKeys,
+    error::Error,
+    math::{PoolReserves, SwapFee},
+    state::{amm_info::AmmInfo, open_orders::OpenOrders},
+    utils::{client::Client, pubkey::pubkey_from_str},
+};
+
+/// Snapshot of the accounts the AMM program reads to price deposits,
+/// withdrawals and swaps.
+#[derive(Clone, Debug)]
+pub struct PoolState {
+    pub amm_info: AmmInfo,
+    pub reserves: PoolReserves,
+}
+
+impl PoolState {
+    /// LP supply as tracked by the AMM, which is what the program prices
+    /// against (tokens burned outside the program don't reduce it).
+    pub fn lp_supply(&self) -> u64 {
+        self.amm_info.lp_amount
+    }
+
+    /// Swap fee configured on the pool.
+    pub fn swap_fee(&self) -> SwapFee {
+        SwapFee {
+            numerator: self.amm_info.fees.swap_fee_numerator,
+            denominator: self.amm_info.fees.swap_fee_denominator,
+        }
+    }
+}
+
+/// Fetch the pool, its vaults and its open orders in a single RPC call.
+pub async fn fetch_pool_state(client: &Client, pool_keys: &PoolKeys) -> Result<PoolState, Error> {
+    let accounts = client
+        .get_multiple_accounts(&[
+            pubkey_from_str(&pool_keys.id)?,
+            pubkey_from_str(&pool_keys.vault.A)?,
+            pubkey_from_str(&pool_keys.vault.B)?,
+            pubkey_from_str(&pool_keys.openOrders)?,
+        ])
+        .await?;
+
+    let amm_info = AmmInfo::from_account_data(&accounts[0].data)?;
+    let base_vault = TokenAccount::unpack(&accounts[1].data)
+        .map_err(|e| Error::AccountDecode(format!("Base vault: {}", e)))?;
+    let quote_vault = TokenAccount::unpack(&accounts[2].data)
+        .map_err(|e| Error::AccountDecode(format!("Quote vault: {}", e)))?;
+
+    // Funds on the order book only count while the pool may place orders
+    let (open_orders_base, open_orders_quote) = if amm_info.orderbook_enabled() {
+        let open_orders = OpenOrders::from_account_data(&accounts[3].data)?;
+        (open_orders.native_coin_total, open_orders.native_pc_total)
+    } else {
+        (0, 0)
+    };
+
+    let reserves = PoolReserves::new(
+        base_vault.amount + open_orders_base,
+        quote_vault.amount + open_orders_quote,
+        amm_info.state_data.need_take_pnl_coin,
+        amm_info.state_data.need_take_pnl_pc,
+    )?;
+
+    Ok(PoolState { amm_info, reserves })
+}
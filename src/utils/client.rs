@@ -1,28 +1,59 @@
-use solana_client::{rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{
+        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig,
+        RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+    },
+    rpc_filter::RpcFilterType,
+    rpc_request::TokenAccountsFilter,
+    rpc_response::RpcSimulateTransactionResult,
+};
 use solana_sdk::{
+    account::Account,
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
     commitment_config::CommitmentConfig,
     hash::Hash,
     instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
     signer::EncodableKey,
-    system_instruction::create_account,
-    transaction::Transaction,
+    system_instruction::{create_account, transfer},
+    transaction::VersionedTransaction,
 };
 use spl_associated_token_account::{
-    get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+    get_associated_token_address_with_program_id,
+    instruction::{create_associated_token_account, create_associated_token_account_idempotent},
 };
 use spl_token::{
-    instruction::{close_account, initialize_account},
+    instruction::{close_account, initialize_account, sync_native},
     solana_program::program_pack::Pack,
     state::Account as TokenAccount,
-    ID as TOKEN_PROGRAM_ID,
 };
+use spl_token_2022::{extension::StateWithExtensions, state::Account as Token2022Account};
 use tracing::debug;
 
-use crate::{config::Config, error::Error};
+use crate::{
+    config::Config,
+    error::Error,
+    utils::{
+        amount::amount_raw_to_display,
+        compute_budget::{
+            add_compute_budget, compute_unit_limit, estimate_priority_fee, transaction_fee,
+            ComputeBudgetConfig, PriorityFeeConfig, MAX_COMPUTE_UNIT_LIMIT,
+        },
+        pubkey::pubkey_from_str,
+    },
+};
 
 pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+/// Maximum serialized transaction size accepted by the network.
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
 
 #[derive(Debug)]
 pub struct TokenAccountInfo {
@@ -38,19 +69,101 @@ pub enum TokenSide {
     Out,
 }
 
+/// How SOL is wrapped when a command needs WSOL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsolMode {
+    /// Wrap into the payer's WSOL associated token account with `sync_native`
+    #[default]
+    Ata,
+    /// Wrap into a fresh keypair account closed at the end of the transaction
+    TempAccount,
+}
+
+/// WSOL handling chosen for a command.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WsolOptions {
+    pub mode: WsolMode,
+    /// Leave the wrapped SOL in the WSOL ATA instead of unwrapping it
+    pub keep: bool,
+}
+
 pub struct HandleTokenAccountParams {
     pub side: TokenSide,
     pub amount: u64,
     pub mint: Pubkey,
+    /// Token program owning `mint`
+    pub token_program: Pubkey,
     pub token_account: Option<Pubkey>,
     pub bypass_associated_check: bool,
     pub skip_close_account: bool,
     pub check_create_ata_owner: bool,
+    pub wsol_mode: WsolMode,
+}
+
+/// Balance of a token account owned by the payer.
+#[derive(Debug, Clone)]
+pub struct TokenBalance {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub decimals: i32,
+}
+
+/// Account whose balance is compared across a transaction.
+pub struct WatchedAccount {
+    pub label: String,
+    pub address: Pubkey,
+    pub decimals: i32,
+}
+
+/// Token amount moved by a transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenAmount {
+    pub token: String,
+    pub account: String,
+    pub amount: String,
+    pub raw_amount: u64,
+}
+
+/// Outcome of a sent or simulated transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionResult {
+    /// `None` when the transaction was only simulated
+    pub signature: Option<String>,
+    pub slot: Option<u64>,
+    pub simulated: bool,
+    /// Signature and priority fees, in lamports
+    pub fee_lamports: u64,
+    pub compute_unit_limit: u32,
+    pub compute_unit_price_micro_lamports: u64,
+    /// Balances that went down, SOL excluding the fee
+    pub amounts_in: Vec<TokenAmount>,
+    /// Balances that went up
+    pub amounts_out: Vec<TokenAmount>,
 }
 
 pub struct Client {
     pub rpc_client: RpcClient,
     pub payer: Keypair,
+    /// Lookup tables tried for every transaction
+    pub lookup_tables: Vec<Pubkey>,
+    pub priority_fee: PriorityFeeConfig,
+    /// Headroom added to the simulated compute units
+    pub compute_unit_margin_percentage: u32,
+}
+
+/// Config of a `getProgramAccounts` call matching every filter. Account data
+/// comes back as base64, the RPC refuses base58 for accounts over 128 bytes.
+pub fn program_accounts_config(filters: Vec<RpcFilterType>) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
 }
 
 pub fn get_client(config: &Config) -> Result<Client, Error> {
@@ -61,14 +174,27 @@ pub fn get_client(config: &Config) -> Result<Client, Error> {
 
     let rpc_client = RpcClient::new_with_commitment(rpc_endpoint, CommitmentConfig::confirmed());
 
-    Ok(Client { rpc_client, payer })
+    let lookup_tables = config
+        .lookup_tables
+        .iter()
+        .map(|address| pubkey_from_str(address))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Client {
+        rpc_client,
+        payer,
+        lookup_tables,
+        priority_fee: config.priority_fee.clone(),
+        compute_unit_margin_percentage: config.compute_unit_margin_percentage,
+    })
 }
 
 impl Client {
-    pub fn get_latest_blockhash(&self) -> Result<Hash, Error> {
+    pub async fn get_latest_blockhash(&self) -> Result<Hash, Error> {
         let (blockhash, _) = self
             .rpc_client
             .get_latest_blockhash_with_commitment(CommitmentConfig::finalized())
+            .await
             .map_err(|e| Error::RpcClient(e.to_string()))?;
 
         Ok(blockhash)
@@ -78,19 +204,144 @@ impl Client {
         self.payer.pubkey()
     }
 
-    pub fn get_token_account_balance_ui_amount(&self, address: &Pubkey) -> Result<f64, Error> {
+    pub async fn get_account(&self, address: &Pubkey) -> Result<Account, Error> {
+        self.rpc_client
+            .get_account(address)
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))
+    }
+
+    pub async fn get_balance(&self, address: &Pubkey) -> Result<u64, Error> {
+        self.rpc_client
+            .get_balance(address)
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))
+    }
+
+    pub async fn get_minimum_balance_for_rent_exemption(
+        &self,
+        data_len: usize,
+    ) -> Result<u64, Error> {
+        self.rpc_client
+            .get_minimum_balance_for_rent_exemption(data_len)
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))
+    }
+
+    pub async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Account>, Error> {
+        let accounts = self
+            .rpc_client
+            .get_multiple_accounts(addresses)
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))?;
+
+        accounts
+            .into_iter()
+            .zip(addresses)
+            .map(|(account, address)| {
+                account.ok_or_else(|| Error::RpcClient(format!("Account not found: {}", address)))
+            })
+            .collect()
+    }
+
+    /// Like `get_multiple_accounts`, with `None` for accounts that don't exist.
+    pub async fn get_multiple_accounts_or_none(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, Error> {
+        self.rpc_client
+            .get_multiple_accounts(addresses)
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))
+    }
+
+    /// Accounts owned by `program_id` matching every filter.
+    pub async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, Account)>, Error> {
+        self.rpc_client
+            .get_program_accounts_with_config(program_id, program_accounts_config(filters))
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))
+    }
+
+    pub async fn get_recent_prioritization_fees(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<u64>, Error> {
+        let fees = self
+            .rpc_client
+            .get_recent_prioritization_fees(addresses)
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))?;
+
+        Ok(fees.into_iter().map(|fee| fee.prioritization_fee).collect())
+    }
+
+    /// Non-empty SPL Token accounts owned by the payer.
+    pub async fn get_token_balances(&self) -> Result<Vec<TokenBalance>, Error> {
+        let accounts = self
+            .rpc_client
+            .get_token_accounts_by_owner(
+                &self.get_payer_pubkey(),
+                TokenAccountsFilter::ProgramId(spl_token::ID),
+            )
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))?;
+
+        let mut balances = vec![];
+        for keyed_account in accounts {
+            // The RPC returns the accounts jsonParsed
+            let data = serde_json::to_value(&keyed_account.account.data)
+                .map_err(|e| Error::AccountDecode(e.to_string()))?;
+            let info = &data["parsed"]["info"];
+            let decode_error =
+                || Error::AccountDecode(format!("Token account {}", keyed_account.pubkey));
+
+            let amount = info["tokenAmount"]["amount"]
+                .as_str()
+                .and_then(|amount| amount.parse::<u64>().ok())
+                .ok_or_else(decode_error)?;
+            if amount == 0 {
+                continue;
+            }
+
+            balances.push(TokenBalance {
+                address: pubkey_from_str(&keyed_account.pubkey)?,
+                mint: pubkey_from_str(info["mint"].as_str().ok_or_else(decode_error)?)?,
+                amount,
+                decimals: info["tokenAmount"]["decimals"]
+                    .as_i64()
+                    .ok_or_else(decode_error)? as i32,
+            });
+        }
+
+        Ok(balances)
+    }
+
+    pub async fn get_token_account_balance_ui_amount(
+        &self,
+        address: &Pubkey,
+    ) -> Result<f64, Error> {
         let ui_token_amount = self
             .rpc_client
             .get_token_account_balance(address)
+            .await
             .map_err(|e| Error::RpcClient(e.to_string()))?;
 
         Ok(ui_token_amount.ui_amount.unwrap_or(0.0))
     }
 
-    pub fn get_token_account_balance_string(&self, address: &Pubkey) -> Result<String, Error> {
+    pub async fn get_token_account_balance_string(
+        &self,
+        address: &Pubkey,
+    ) -> Result<String, Error> {
         let ui_token_amount = self
             .rpc_client
             .get_token_account_balance(address)
+            .await
             .map_err(|e| Error::RpcClient(e.to_string()))?;
 
         Ok(ui_token_amount.amount)
@@ -104,38 +355,41 @@ impl Client {
             side,
             amount,
             mint,
+            token_program,
             token_account,
             bypass_associated_check,
             skip_close_account,
             check_create_ata_owner,
+            wsol_mode,
         } = params;
 
         // Get ATA for this mint
         let ata = get_associated_token_address_with_program_id(
             &self.get_payer_pubkey(),
             &mint,
-            &TOKEN_PROGRAM_ID,
+            &token_program,
         );
 
+        if mint.to_string() == WSOL_MINT && wsol_mode == WsolMode::Ata {
+            return self
+                .handle_wsol_ata(&side, amount, &ata, skip_close_account)
+                .await;
+        }
+
         // Handle WSOL case
         if mint.to_string() == WSOL_MINT {
             let wsol_keypair = Keypair::new();
             let wsol_account = wsol_keypair.pubkey();
 
-            // Get rent-exempt amount
-            let min_balance = self
-                .rpc_client
-                .get_minimum_balance_for_rent_exemption(TokenAccount::LEN as usize)
-                .map_err(|e| Error::RpcClient(e.to_string()))?;
+            // Get rent-exempt amount and SOL balance
+            let payer = self.get_payer_pubkey();
+            let (min_balance, sol_balance) = tokio::try_join!(
+                self.get_minimum_balance_for_rent_exemption(TokenAccount::LEN),
+                self.get_balance(&payer),
+            )?;
             let transaction_fee = 5000; // Default fee
             let total_needed = amount + min_balance + transaction_fee;
 
-            // Check SOL balance
-            let sol_balance = self
-                .rpc_client
-                .get_balance(&self.get_payer_pubkey())
-                .map_err(|e| Error::RpcClient(e.to_string()))?;
-
             if sol_balance < total_needed {
                 return Err(Error::InsufficientBalance(format!(
                     "Insufficient SOL for wrap. Need {}, have {}",
@@ -205,14 +459,18 @@ impl Client {
                 &self.get_payer_pubkey(),
                 &self.get_payer_pubkey(),
                 &mint,
-                &TOKEN_PROGRAM_ID,
+                &token_program,
             );
 
             if check_create_ata_owner {
                 // Check if ATA exists and is valid
-                if let Ok(account) = self.rpc_client.get_account(&ata) {
-                    if let Ok(token_account) = TokenAccount::unpack(&account.data) {
-                        if token_account.mint == mint
+                if let Ok(account) = self.rpc_client.get_account(&ata).await {
+                    if let Ok(token_account) =
+                        StateWithExtensions::<Token2022Account>::unpack(&account.data)
+                    {
+                        let token_account = token_account.base;
+                        if account.owner == token_program
+                            && token_account.mint == mint
                             && token_account.owner == self.get_payer_pubkey()
                         {
                             return Ok(TokenAccountInfo {
@@ -252,32 +510,472 @@ impl Client {
         })
     }
 
-    pub fn send_transaction(
+    /// Wrap SOL in the payer's WSOL ATA, creating it if needed, and close it
+    /// at the end unless `skip_close_account` is set. An ATA that already
+    /// held WSOL is never closed, closing would unwrap that balance too.
+    async fn handle_wsol_ata(
+        &self,
+        side: &TokenSide,
+        amount: u64,
+        ata: &Pubkey,
+        skip_close_account: bool,
+    ) -> Result<TokenAccountInfo, Error> {
+        let payer = self.get_payer_pubkey();
+        let mut start_instructions = vec![create_associated_token_account_idempotent(
+            &payer,
+            &payer,
+            &spl_token::native_mint::ID,
+            &spl_token::ID,
+        )];
+        let mut end_instructions = vec![];
+        let mut instruction_types = vec!["CreateWSolAta".to_string()];
+
+        let ata_account = self.get_multiple_accounts_or_none(&[*ata]).await?.remove(0);
+        let held_wsol = ata_account.as_ref().is_some_and(|account| {
+            !TokenAccount::unpack(&account.data).is_ok_and(|wsol| wsol.amount == 0)
+        });
+
+        if matches!(side, TokenSide::In) && amount > 0 {
+            let (min_balance, sol_balance) = tokio::try_join!(
+                self.get_minimum_balance_for_rent_exemption(TokenAccount::LEN),
+                self.get_balance(&payer),
+            )?;
+            // Rent is only needed when the ATA doesn't exist yet
+            let rent = if ata_account.is_some() {
+                0
+            } else {
+                min_balance
+            };
+            let transaction_fee = 5000; // Default fee
+            let total_needed = amount + rent + transaction_fee;
+
+            if sol_balance < total_needed {
+                return Err(Error::InsufficientBalance(format!(
+                    "Insufficient SOL for wrap. Need {}, have {}",
+                    total_needed, sol_balance
+                )));
+            }
+
+            start_instructions.push(transfer(&payer, ata, amount));
+            start_instructions.push(
+                sync_native(&spl_token::ID, ata).map_err(|e| Error::RpcClient(e.to_string()))?,
+            );
+            instruction_types.push("WrapSol".to_string());
+        }
+
+        if held_wsol {
+            debug!("Keeping WSOL ATA {}, it held WSOL before", ata);
+        } else if !skip_close_account {
+            end_instructions.push(
+                close_account(&spl_token::ID, ata, &payer, &payer, &[])
+                    .map_err(|e| Error::RpcClient(e.to_string()))?,
+            );
+            instruction_types.push("CloseWSolAta".to_string());
+        }
+
+        Ok(TokenAccountInfo {
+            token_account: *ata,
+            start_instructions,
+            end_instructions,
+            instruction_types,
+            additional_signers: vec![],
+        })
+    }
+
+    /// Fetch and decode address lookup tables.
+    pub async fn get_lookup_tables(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>, Error> {
+        if addresses.is_empty() {
+            return Ok(vec![]);
+        }
+
+        self.get_multiple_accounts(addresses)
+            .await?
+            .iter()
+            .zip(addresses)
+            .map(|(account, address)| {
+                let table = AddressLookupTable::deserialize(&account.data).map_err(|e| {
+                    Error::AccountDecode(format!("Lookup table {}: {}", address, e))
+                })?;
+                Ok(AddressLookupTableAccount {
+                    key: *address,
+                    addresses: table.addresses.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    /// Decode the client's lookup tables along with `lookup_tables`.
+    async fn load_lookup_tables(
+        &self,
+        lookup_tables: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>, Error> {
+        let mut table_addresses = self.lookup_tables.clone();
+        for address in lookup_tables {
+            if !table_addresses.contains(address) {
+                table_addresses.push(*address);
+            }
+        }
+
+        self.get_lookup_tables(&table_addresses).await
+    }
+
+    /// Compile the smaller of a legacy and a v0 message.
+    fn compile_message(
         &self,
         instructions: &[Instruction],
+        lookup_tables: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+    ) -> Result<VersionedMessage, Error> {
+        let payer = self.get_payer_pubkey();
+        let legacy = VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(&payer),
+            &recent_blockhash,
+        ));
+
+        if lookup_tables.is_empty() {
+            return Ok(legacy);
+        }
+
+        let v0 = VersionedMessage::V0(
+            v0::Message::try_compile(&payer, instructions, lookup_tables, recent_blockhash)
+                .map_err(|e| Error::Transaction(e.to_string()))?,
+        );
+
+        let legacy_size = legacy.serialize().len();
+        let v0_size = v0.serialize().len();
+        debug!(
+            "Message sizes: legacy {} bytes, v0 {} bytes",
+            legacy_size, v0_size
+        );
+
+        Ok(if v0_size < legacy_size { v0 } else { legacy })
+    }
+
+    /// Sign `user_instructions` prefixed with the given compute budget.
+    fn sign_transaction(
+        &self,
+        user_instructions: &[Instruction],
+        signers: &[&Keypair],
+        lookup_tables: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+        compute_budget: &ComputeBudgetConfig,
+    ) -> Result<VersionedTransaction, Error> {
+        let mut instructions = add_compute_budget(compute_budget)
+            .into_iter()
+            .map(|ix| ix.instruction)
+            .collect::<Vec<Instruction>>();
+        instructions.extend_from_slice(user_instructions);
+
+        let message = self.compile_message(&instructions, lookup_tables, recent_blockhash)?;
+
+        // Compact signature count followed by the signatures and the message
+        let num_signatures = message.header().num_required_signatures as usize;
+        let transaction_size = 1 + num_signatures * 64 + message.serialize().len();
+        if transaction_size > MAX_TRANSACTION_SIZE {
+            return Err(Error::Transaction(format!(
+                "Transaction too large: {} bytes, limit is {}",
+                transaction_size, MAX_TRANSACTION_SIZE
+            )));
+        }
+
+        let transaction = VersionedTransaction::try_new(message, signers)
+            .map_err(|e| Error::Transaction(e.to_string()))?;
+        debug!(
+            "Transaction created with {} instructions, {} signers, {} bytes",
+            instructions.len(),
+            signers.len(),
+            transaction_size
+        );
+
+        Ok(transaction)
+    }
+
+    /// Simulate a transaction, turning a failed simulation into an error
+    /// carrying the program logs.
+    pub async fn simulate_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<RpcSimulateTransactionResult, Error> {
+        self.simulate_transaction_with_accounts(transaction, None)
+            .await
+    }
+
+    /// Simulate a transaction and return the post-state of `accounts`.
+    pub async fn simulate_transaction_with_accounts(
+        &self,
+        transaction: &VersionedTransaction,
+        accounts: Option<RpcSimulateTransactionAccountsConfig>,
+    ) -> Result<RpcSimulateTransactionResult, Error> {
+        let simulation = self
+            .rpc_client
+            .simulate_transaction_with_config(
+                transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(CommitmentConfig::processed()),
+                    accounts,
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )
+            .await
+            .map_err(|e| Error::RpcClient(e.to_string()))?
+            .value;
+
+        if let Some(err) = simulation.err {
+            return Err(Error::Simulation {
+                err: err.to_string(),
+                logs: simulation.logs.unwrap_or_default(),
+            });
+        }
+
+        Ok(simulation)
+    }
+
+    /// Build the signed transaction for `user_instructions`, priced from the
+    /// recent priority fees and limited to the simulated compute units plus
+    /// the configured margin.
+    pub async fn build_transaction(
+        &self,
+        user_instructions: &[Instruction],
         additional_signers: &[Keypair],
-    ) -> Result<Signature, Error> {
-        debug!("Getting latest blockhash...");
-        let recent_blockhash = self.get_latest_blockhash()?;
+        lookup_tables: &[Pubkey],
+    ) -> Result<VersionedTransaction, Error> {
+        let (transaction, _) = self
+            .build_transaction_with_budget(user_instructions, additional_signers, lookup_tables)
+            .await?;
+        Ok(transaction)
+    }
+
+    /// Like `build_transaction`, also returning the compute budget the
+    /// transaction was signed with.
+    async fn build_transaction_with_budget(
+        &self,
+        user_instructions: &[Instruction],
+        additional_signers: &[Keypair],
+        lookup_tables: &[Pubkey],
+    ) -> Result<(VersionedTransaction, ComputeBudgetConfig), Error> {
+        debug!("Getting latest blockhash, lookup tables and priority fees...");
+        let (recent_blockhash, lookup_tables, micro_lamports) = tokio::try_join!(
+            self.get_latest_blockhash(),
+            self.load_lookup_tables(lookup_tables),
+            estimate_priority_fee(self, user_instructions, &self.priority_fee),
+        )?;
         debug!("Got blockhash: {}", recent_blockhash);
 
         let mut signers = vec![&self.payer];
         signers.extend(additional_signers);
 
-        debug!(
-            "Creating transaction with {} instructions...",
-            instructions.len()
-        );
-        let transaction = Transaction::new_signed_with_payer(
-            instructions,
-            Some(&self.get_payer_pubkey()),
+        debug!("Simulating transaction to size the compute unit limit...");
+        let simulation = self
+            .simulate_transaction(&self.sign_transaction(
+                user_instructions,
+                &signers,
+                &lookup_tables,
+                recent_blockhash,
+                &ComputeBudgetConfig {
+                    micro_lamports: Some(micro_lamports),
+                    units: Some(MAX_COMPUTE_UNIT_LIMIT),
+                },
+            )?)
+            .await?;
+        let units_consumed = simulation.units_consumed.ok_or_else(|| {
+            Error::RpcClient("Simulation did not report units consumed".to_string())
+        })?;
+        let units = compute_unit_limit(units_consumed, self.compute_unit_margin_percentage);
+        debug!("Units consumed: {}, limit: {}", units_consumed, units);
+
+        let compute_budget = ComputeBudgetConfig {
+            micro_lamports: Some(micro_lamports),
+            units: Some(units),
+        };
+        let transaction = self.sign_transaction(
+            user_instructions,
             &signers,
+            &lookup_tables,
             recent_blockhash,
+            &compute_budget,
+        )?;
+
+        Ok((transaction, compute_budget))
+    }
+
+    /// Raw balance of a watched account: lamports for the payer, token amount
+    /// otherwise, and 0 for accounts that don't exist.
+    fn watched_balance(&self, address: &Pubkey, account: Option<&Account>) -> u64 {
+        match account {
+            None => 0,
+            Some(account) if address == &self.get_payer_pubkey() => account.lamports,
+            Some(account) => StateWithExtensions::<Token2022Account>::unpack(&account.data)
+                .map(|token_account| token_account.base.amount)
+                .unwrap_or(0),
+        }
+    }
+
+    /// Build the exact transaction for `user_instructions` and send it, or
+    /// only simulate it when `dry_run` is set. Either way the payer's SOL and
+    /// the `watched` accounts are compared before and after to report the
+    /// amounts that went in and out.
+    pub async fn execute_transaction(
+        &self,
+        user_instructions: &[Instruction],
+        additional_signers: &[Keypair],
+        lookup_tables: &[Pubkey],
+        watched: &[WatchedAccount],
+        dry_run: bool,
+    ) -> Result<TransactionResult, Error> {
+        // A transaction of only compute budget instructions would still pay fees
+        if user_instructions.is_empty() {
+            return Err(Error::Transaction("No instructions to send".to_string()));
+        }
+
+        let mut watched_accounts = vec![WatchedAccount {
+            label: "SOL".to_string(),
+            address: self.get_payer_pubkey(),
+            decimals: 9,
+        }];
+        for account in watched {
+            if !watched_accounts
+                .iter()
+                .any(|w| w.address == account.address)
+            {
+                watched_accounts.push(WatchedAccount {
+                    label: account.label.clone(),
+                    address: account.address,
+                    decimals: account.decimals,
+                });
+            }
+        }
+        let addresses = watched_accounts
+            .iter()
+            .map(|account| account.address)
+            .collect::<Vec<_>>();
+
+        // Nothing is sent yet, so the balances can be read while building
+        let ((transaction, compute_budget), before) = tokio::try_join!(
+            self.build_transaction_with_budget(
+                user_instructions,
+                additional_signers,
+                lookup_tables
+            ),
+            async {
+                self.rpc_client
+                    .get_multiple_accounts(&addresses)
+                    .await
+                    .map_err(|e| Error::RpcClient(e.to_string()))
+            },
+        )?;
+        let compute_unit_limit = compute_budget.units.unwrap_or(MAX_COMPUTE_UNIT_LIMIT);
+        let compute_unit_price = compute_budget.micro_lamports.unwrap_or(0);
+        let fee_lamports = transaction_fee(
+            transaction.message.header().num_required_signatures,
+            compute_unit_price,
+            compute_unit_limit,
         );
-        debug!("Transaction created with {} signers", signers.len());
 
+        let (signature, slot, after) = if dry_run {
+            let simulation = self
+                .simulate_transaction_with_accounts(
+                    &transaction,
+                    Some(RpcSimulateTransactionAccountsConfig {
+                        // The RPC returns base64 account data by default
+                        encoding: None,
+                        addresses: addresses.iter().map(|a| a.to_string()).collect(),
+                    }),
+                )
+                .await?;
+            let after = simulation
+                .accounts
+                .ok_or_else(|| Error::RpcClient("Simulation returned no accounts".to_string()))?
+                .into_iter()
+                .map(|account| account.and_then(|account| account.decode::<Account>()))
+                .collect::<Vec<_>>();
+            (None, None, after)
+        } else {
+            let signature = self
+                .send_built_transaction(&transaction, user_instructions)
+                .await?;
+            let slot = self
+                .rpc_client
+                .get_signature_statuses(&[signature])
+                .await
+                .ok()
+                .and_then(|statuses| statuses.value.into_iter().next().flatten())
+                .map(|status| status.slot);
+            let after = self
+                .rpc_client
+                .get_multiple_accounts(&addresses)
+                .await
+                .map_err(|e| Error::RpcClient(e.to_string()))?;
+            (Some(signature.to_string()), slot, after)
+        };
+
+        let mut amounts_in = vec![];
+        let mut amounts_out = vec![];
+        for (account, (before, after)) in watched_accounts
+            .into_iter()
+            .zip(before.iter().zip(after.iter()))
+        {
+            let before = self.watched_balance(&account.address, before.as_ref());
+            let mut after = self.watched_balance(&account.address, after.as_ref());
+            // The fee is reported on its own
+            if account.address == self.get_payer_pubkey() {
+                after += fee_lamports;
+            }
+
+            let (amounts, raw_amount) = if after < before {
+                (&mut amounts_in, before - after)
+            } else {
+                (&mut amounts_out, after - before)
+            };
+            if raw_amount > 0 {
+                amounts.push(TokenAmount {
+                    token: account.label,
+                    account: account.address.to_string(),
+                    amount: amount_raw_to_display(raw_amount, account.decimals).to_string(),
+                    raw_amount,
+                });
+            }
+        }
+
+        Ok(TransactionResult {
+            signature,
+            slot,
+            simulated: dry_run,
+            fee_lamports,
+            compute_unit_limit,
+            compute_unit_price_micro_lamports: compute_unit_price,
+            amounts_in,
+            amounts_out,
+        })
+    }
+
+    /// Sign and send `user_instructions`, prefixed with the compute budget
+    /// instructions.
+    pub async fn send_transaction(
+        &self,
+        user_instructions: &[Instruction],
+        additional_signers: &[Keypair],
+        lookup_tables: &[Pubkey],
+    ) -> Result<Signature, Error> {
+        let transaction = self
+            .build_transaction(user_instructions, additional_signers, lookup_tables)
+            .await?;
+        self.send_built_transaction(&transaction, user_instructions)
+            .await
+    }
+
+    async fn send_built_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+        user_instructions: &[Instruction],
+    ) -> Result<Signature, Error> {
         // Print info about each instruction
-        for (i, instruction) in instructions.iter().enumerate() {
+        for (i, instruction) in user_instructions.iter().enumerate() {
             debug!(
                 "Instruction {}: Program {} with {} accounts",
                 i,
@@ -290,7 +988,7 @@ impl Client {
         match self
             .rpc_client
             .send_and_confirm_transaction_with_spinner_and_config(
-                &transaction,
+                transaction,
                 CommitmentConfig::confirmed(),
                 RpcSendTransactionConfig {
                     skip_preflight: false,
@@ -299,7 +997,9 @@ impl Client {
                     max_retries: Some(3),
                     min_context_slot: None,
                 },
-            ) {
+            )
+            .await
+        {
             Ok(sig) => {
                 debug!("Transaction confirmed with signature: {}", sig);
                 Ok(sig)
@@ -307,7 +1007,7 @@ impl Client {
             Err(e) => {
                 debug!("Transaction failed: {}", e);
                 // Try to get more information about the failure
-                if let Ok(simulation) = self.rpc_client.simulate_transaction(&transaction) {
+                if let Ok(simulation) = self.rpc_client.simulate_transaction(transaction).await {
                     debug!("Simulation logs: {:?}", simulation.value.logs);
                 }
                 Err(Error::RpcClient(e.to_string()))
@@ -1,5 +1,65 @@
-use solana_program::instruction::Instruction;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use tracing::{debug, warn};
+
+use crate::{error::Error, utils::client::Client};
+
+/// Maximum compute unit limit of a transaction, used while simulating.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Base fee charged per signature.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// Maximum number of accounts accepted by `getRecentPrioritizationFees`.
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Percentile of the recent prioritization fees to pay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityFeeStrategy {
+    P50,
+    #[default]
+    P75,
+    P90,
+}
+
+impl PriorityFeeStrategy {
+    fn percentile(&self) -> usize {
+        match self {
+            PriorityFeeStrategy::P50 => 50,
+            PriorityFeeStrategy::P75 => 75,
+            PriorityFeeStrategy::P90 => 90,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityFeeConfig {
+    #[serde(default)]
+    pub strategy: PriorityFeeStrategy,
+    /// Lower bound on the compute unit price, in micro-lamports
+    #[serde(default)]
+    pub min_micro_lamports: u64,
+    /// Upper bound on the compute unit price, in micro-lamports
+    #[serde(default = "default_max_micro_lamports")]
+    pub max_micro_lamports: u64,
+}
+
+fn default_max_micro_lamports() -> u64 {
+    1_000_000
+}
+
+impl Default for PriorityFeeConfig {
+    fn default() -> Self {
+        Self {
+            strategy: PriorityFeeStrategy::default(),
+            min_micro_lamports: 0,
+            max_micro_lamports: default_max_micro_lamports(),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ComputeBudgetConfig {
@@ -34,6 +94,79 @@ pub fn add_compute_budget(config: &ComputeBudgetConfig) -> Vec<InstructionWithTy
     instructions
 }
 
+/// Compute unit limit covering `units_consumed` plus `margin_percentage`,
+/// capped at the transaction maximum.
+pub fn compute_unit_limit(units_consumed: u64, margin_percentage: u32) -> u32 {
+    let units = units_consumed.saturating_mul(100 + margin_percentage as u64) / 100;
+    units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// Fee paid by a transaction with `num_signatures` signatures, priced at
+/// `micro_lamports` per compute unit up to `units`.
+pub fn transaction_fee(num_signatures: u8, micro_lamports: u64, units: u32) -> u64 {
+    let priority_fee = (micro_lamports as u128 * units as u128).div_ceil(1_000_000) as u64;
+    LAMPORTS_PER_SIGNATURE * num_signatures as u64 + priority_fee
+}
+
+/// Writable accounts the instructions touch, excluding signers, which are
+/// the accounts whose write locks are contended.
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = vec![];
+    for account in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if account.is_writable && !account.is_signer && !accounts.contains(&account.pubkey) {
+            accounts.push(account.pubkey);
+        }
+    }
+    accounts.truncate(MAX_FEE_ACCOUNTS);
+    accounts
+}
+
+/// Nearest-rank percentile of `fees`, or 0 when there are none.
+pub fn fee_percentile(fees: &mut [u64], strategy: PriorityFeeStrategy) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+
+    fees.sort_unstable();
+    let rank = (strategy.percentile() * fees.len()).div_ceil(100);
+    fees[rank.saturating_sub(1)]
+}
+
+/// Estimate the compute unit price from the recent prioritization fees paid
+/// for the writable accounts of `instructions`, clamped to the config caps.
+pub async fn estimate_priority_fee(
+    client: &Client,
+    instructions: &[Instruction],
+    config: &PriorityFeeConfig,
+) -> Result<u64, Error> {
+    if config.min_micro_lamports > config.max_micro_lamports {
+        return Err(Error::Config(format!(
+            "Minimum priority fee {} exceeds maximum {}",
+            config.min_micro_lamports, config.max_micro_lamports
+        )));
+    }
+
+    let accounts = writable_accounts(instructions);
+    let estimate = match client.get_recent_prioritization_fees(&accounts).await {
+        Ok(mut fees) => fee_percentile(&mut fees, config.strategy),
+        Err(e) => {
+            warn!("Failed estimating priority fee, using minimum: {}", e);
+            config.min_micro_lamports
+        }
+    };
+
+    let micro_lamports = estimate.clamp(config.min_micro_lamports, config.max_micro_lamports);
+    debug!(
+        "Priority fee: {:?} estimate {} over {} accounts, using {}",
+        config.strategy,
+        estimate,
+        accounts.len(),
+        micro_lamports
+    );
+
+    Ok(micro_lamports)
+}
+
 // Example usage
 pub fn example_usage() {
     let config = ComputeBudgetConfig {
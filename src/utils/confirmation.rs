@@ -1,14 +1,58 @@
 use std::io;
 
-/// Get confirmation from the user
-pub fn get_confirmation(message: &str) -> () {
-    println!("{}", message);
-    println!("Are you sure you want to proceed? (Y/n)");
+use tracing::{info, warn};
+
+use crate::{
+    config::Config,
+    error::Error,
+    pool_source::PoolSource,
+    utils::guardrails::{notional_usd, Trade},
+};
+
+/// Get confirmation for a write command. Prompts on stdin, or with `yes`
+/// checks the configured guardrails instead and fails on any violation. Dry
+/// runs only log the message. `pool_source` prices the notional limit.
+pub async fn get_confirmation(
+    config: &Config,
+    pool_source: &impl PoolSource,
+    message: &str,
+    trade: &Trade<'_>,
+    yes: bool,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let guardrails = &config.guardrails;
+    let notional = match guardrails.max_notional_usd {
+        Some(_) => Some(notional_usd(pool_source, trade).await?),
+        None => None,
+    };
+    let violations = guardrails.violations(trade, notional);
+
+    if dry_run || yes {
+        info!("{}", message);
+    } else {
+        eprintln!("{}", message);
+    }
+    for violation in &violations {
+        warn!("Guardrail: {}", violation);
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    if yes {
+        if !violations.is_empty() {
+            return Err(Error::Guardrail(violations.join(", ")));
+        }
+        return Ok(());
+    }
+
+    eprintln!("Are you sure you want to proceed? (Y/n)");
     let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
+    io::stdin().read_line(&mut input)?;
     if input.trim() == "Y" {
-        ()
+        Ok(())
     } else {
-        std::process::exit(0);
+        Err(Error::Cancelled)
     }
 }
@@ -0,0 +1,105 @@
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::fetch_pool_keys::PoolKeys, error::Error, pool_source::PoolSource,
+    utils::amount::amount_raw_to_display,
+};
+
+/// Limits enforced on write commands run with `--yes`. Unset limits and empty
+/// lists allow anything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuardrailsConfig {
+    /// Highest slippage accepted, in percent
+    pub max_slippage_percentage: Option<f64>,
+    /// Highest value a transaction may put at risk, in USD
+    pub max_notional_usd: Option<f64>,
+    /// Pools that may be traded
+    pub allowed_pool_ids: Vec<String>,
+    /// Mints both sides of a traded pool must be in
+    pub allowed_mints: Vec<String>,
+}
+
+/// What a write command is about to do, as checked by the guardrails.
+pub struct Trade<'a> {
+    pub pool_keys: &'a PoolKeys,
+    pub slippage_percentage: f64,
+    /// Raw base amount put at risk
+    pub base_amount: u64,
+    /// Raw quote amount put at risk
+    pub quote_amount: u64,
+}
+
+impl GuardrailsConfig {
+    /// Rules `trade` breaks, given its value in USD when it is known.
+    pub fn violations(&self, trade: &Trade, notional_usd: Option<f64>) -> Vec<String> {
+        let mut violations = vec![];
+
+        if let Some(max_slippage) = self.max_slippage_percentage {
+            if trade.slippage_percentage > max_slippage {
+                violations.push(format!(
+                    "slippage {}% is above the {}% limit",
+                    trade.slippage_percentage, max_slippage
+                ));
+            }
+        }
+
+        if let (Some(max_notional), Some(notional)) = (self.max_notional_usd, notional_usd) {
+            if notional > max_notional {
+                violations.push(format!(
+                    "notional ${:.2} is above the ${:.2} limit",
+                    notional, max_notional
+                ));
+            }
+        }
+
+        if !self.allowed_pool_ids.is_empty() && !self.allowed_pool_ids.contains(&trade.pool_keys.id)
+        {
+            violations.push(format!("pool {} is not allowed", trade.pool_keys.id));
+        }
+
+        if !self.allowed_mints.is_empty() {
+            for mint in [&trade.pool_keys.mintA, &trade.pool_keys.mintB] {
+                if !self.allowed_mints.contains(&mint.address) {
+                    violations.push(format!("mint {} is not allowed", mint.label()));
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// Value of `trade` in USD, pricing each side at half the pool TVL. Fails
+/// when a side put at risk can't be priced, rather than valuing it at zero.
+pub async fn notional_usd(pool_source: &impl PoolSource, trade: &Trade<'_>) -> Result<f64, Error> {
+    let pool = pool_source.pool_info(&trade.pool_keys.id).await?;
+
+    let side_value = |amount: u64, decimals: i32, pool_amount: f64| {
+        if amount == 0 {
+            return Ok(0.0);
+        }
+        let price = pool.tvl / 2.0 / pool_amount;
+        if !price.is_finite() || price <= 0.0 {
+            return Err(Error::Guardrail(format!(
+                "can't price pool {} in USD (TVL {}, pool amount {})",
+                trade.pool_keys.id, pool.tvl, pool_amount
+            )));
+        }
+        let amount = amount_raw_to_display(amount, decimals)
+            .to_f64()
+            .unwrap_or_default();
+        Ok(amount * price)
+    };
+
+    Ok(side_value(
+        trade.base_amount,
+        trade.pool_keys.mintA.decimals,
+        pool.mintAmountA,
+    )? + side_value(
+        trade.quote_amount,
+        trade.pool_keys.mintB.decimals,
+        pool.mintAmountB,
+    )?)
+}
@@ -2,5 +2,6 @@ pub mod amount;
 pub mod client;
 pub mod compute_budget;
 pub mod confirmation;
+pub mod guardrails;
 pub mod printer;
 pub mod pubkey;
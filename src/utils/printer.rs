@@ -1,3 +1,22 @@
+use crate::error::Error;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+
+/// Format command results are written to stdout in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Pretty-printed JSON
+    Json,
+    /// Aligned columns, one row per item
+    Table,
+    /// `key: value` lines
+    #[default]
+    Text,
+}
+
 pub fn pretty_print<T: serde::Serialize>(data: &T) -> String {
     let separator = "-".repeat(50);
     let formatted = match serde_json::to_string_pretty(data) {
@@ -12,3 +31,126 @@ pub fn pretty_print<T: serde::Serialize>(data: &T) -> String {
 pub fn pretty_print_simple<T: serde::Serialize>(data: &T) -> String {
     serde_json::to_string_pretty(data).unwrap_or_else(|_| String::from("Error formatting data"))
 }
+
+/// Render a command result in `format`.
+pub fn format_output<T: Serialize>(data: &T, format: OutputFormat) -> Result<String, Error> {
+    let value = serde_json::to_value(data).map_err(|e| Error::Output(e.to_string()))?;
+
+    Ok(match format {
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&value).map_err(|e| Error::Output(e.to_string()))?
+        }
+        OutputFormat::Text => match &value {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| format_lines(&flatten(item)))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            _ => format_lines(&flatten(&value)),
+        },
+        OutputFormat::Table => match &value {
+            Value::Array(items) => format_table(&items.iter().map(flatten).collect::<Vec<_>>()),
+            _ => format_table(
+                &flatten(&value)
+                    .into_iter()
+                    .map(|(key, value)| {
+                        vec![("field".to_string(), key), ("value".to_string(), value)]
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+        },
+    })
+}
+
+/// Write a command result to stdout in `format`. Logs go to stderr, so stdout
+/// only ever holds the result.
+pub fn print_output<T: Serialize>(data: &T, format: OutputFormat) -> Result<(), Error> {
+    let output = format_output(data, format)?;
+    writeln!(std::io::stdout(), "{}", output)?;
+    Ok(())
+}
+
+/// Flatten nested objects and arrays into dotted keys, in field order.
+fn flatten(value: &Value) -> Vec<(String, String)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+        let key = |child: &str| {
+            if prefix.is_empty() {
+                child.to_string()
+            } else {
+                format!("{}.{}", prefix, child)
+            }
+        };
+        match value {
+            Value::Object(map) => {
+                for (child, value) in map {
+                    walk(&key(child), value, out);
+                }
+            }
+            Value::Array(items) => {
+                for (i, value) in items.iter().enumerate() {
+                    walk(&key(&i.to_string()), value, out);
+                }
+            }
+            Value::String(s) => out.push((prefix.to_string(), s.clone())),
+            Value::Null => out.push((prefix.to_string(), String::new())),
+            other => out.push((prefix.to_string(), other.to_string())),
+        }
+    }
+
+    let mut out = vec![];
+    walk("", value, &mut out);
+    out
+}
+
+fn format_lines(fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Columns are the union of the rows' keys, in order of first appearance.
+fn format_table(rows: &[Vec<(String, String)>]) -> String {
+    let mut columns: Vec<&str> = vec![];
+    for (key, _) in rows.iter().flatten() {
+        if !columns.contains(&key.as_str()) {
+            columns.push(key);
+        }
+    }
+
+    let cell = |row: &Vec<(String, String)>, column: &str| {
+        row.iter()
+            .find(|(key, _)| key == column)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default()
+    };
+    let widths = columns
+        .iter()
+        .map(|column| {
+            rows.iter()
+                .map(|row| cell(row, column).chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let line = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![line(columns.iter().map(|c| c.to_string()).collect())];
+    lines.push(line(widths.iter().map(|w| "-".repeat(*w)).collect()));
+    for row in rows {
+        lines.push(line(columns.iter().map(|c| cell(row, c)).collect()));
+    }
+    lines.join("\n")
+}
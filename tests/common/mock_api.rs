@@ -0,0 +1,178 @@
+//! Local stand-in for the Raydium API serving recorded responses.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+pub const POOL_ID: &str = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2";
+/// Answered with an HTTP 500.
+pub const FAILING_ID: &str = "FaiLing111111111111111111111111111111111111";
+/// Answered with `success: false`.
+pub const REJECTED_ID: &str = "RejecTed11111111111111111111111111111111111";
+/// Answered with `data: [null]`, as the API does for ids it doesn't know.
+pub const UNKNOWN_ID: &str = "UnknowN111111111111111111111111111111111111";
+/// Answered with pool info whose fields drifted from the schema.
+pub const DRIFTED_ID: &str = "DrifTed111111111111111111111111111111111111";
+/// Answered with pool info and keys leaving out the optional fields.
+pub const SPARSE_ID: &str = "SparsE1111111111111111111111111111111111111";
+
+pub const WSOL: &str = "So11111111111111111111111111111111111111112";
+pub const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+/// AMM v4 pool trading USDC against WSOL, the reverse of `POOL_ID`.
+pub const REVERSED_ID: &str = "ReversE111111111111111111111111111111111111";
+
+/// Contents of `tests/fixtures/api/<name>`.
+pub fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/api")
+        .join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// HTTP server on a free local port, answering `GET <path>?<query>` from a
+/// fixed table, batching several `ids` from their single-id entries. It lives until the test process exits.
+pub struct MockApi {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockApi {
+    pub fn start(routes: HashMap<String, (u16, String)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock API");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let seen = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                respond(stream, &routes, &seen);
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Server answering the recorded fixtures and the error cases.
+    pub fn recorded() -> Self {
+        let ok = |name: &str| (200, fixture(name));
+        Self::start(HashMap::from([
+            (
+                format!("/pools/info/ids?ids={}", POOL_ID),
+                ok("pools_info_ids.json"),
+            ),
+            (
+                format!("/pools/key/ids?ids={}", POOL_ID),
+                ok("pools_key_ids.json"),
+            ),
+            (
+                format!("/pools/info/ids?ids={}", FAILING_ID),
+                (500, "Internal Server Error".to_string()),
+            ),
+            (
+                format!("/pools/info/ids?ids={}", REJECTED_ID),
+                ok("rejected.json"),
+            ),
+            (
+                format!("/pools/key/ids?ids={}", REJECTED_ID),
+                ok("rejected.json"),
+            ),
+            (
+                format!("/pools/info/ids?ids={}", UNKNOWN_ID),
+                ok("pools_info_ids_unknown.json"),
+            ),
+            (
+                format!("/pools/info/ids?ids={}", DRIFTED_ID),
+                ok("pools_info_ids_schema_drift.json"),
+            ),
+            (
+                format!("/pools/info/ids?ids={}", SPARSE_ID),
+                ok("pools_info_ids_sparse.json"),
+            ),
+            (
+                format!("/pools/key/ids?ids={}", SPARSE_ID),
+                ok("pools_key_ids_sparse.json"),
+            ),
+            (
+                format!(
+                    "/pools/info/mint?mint1={}&mint2={}&poolType=standard&poolSortField=liquidity&sortType=desc&pageSize=100&page=1",
+                    USDC, WSOL
+                ),
+                ok("pools_info_mint.json"),
+            ),
+        ]))
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Request targets received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Answer `<path>?ids=a,b,..` from the single-id routes of `path`, with a
+/// null entry for each id that has none, as the API does.
+fn batch(target: &str, routes: &HashMap<String, (u16, String)>) -> Option<(u16, String)> {
+    let (path, ids) = target.split_once("?ids=")?;
+    let data = ids
+        .split(',')
+        .map(|id| {
+            let (status, body) = routes.get(&format!("{}?ids={}", path, id))?;
+            if *status != 200 {
+                return None;
+            }
+            let response: serde_json::Value = serde_json::from_str(body).ok()?;
+            response["data"].get(0).cloned()
+        })
+        .map(Option::unwrap_or_default)
+        .collect::<Vec<_>>();
+
+    let body = serde_json::json!({ "id": "batch", "success": true, "data": data });
+    Some((200, body.to_string()))
+}
+
+/// Answer one request, logging its target first.
+fn respond(
+    mut stream: TcpStream,
+    routes: &HashMap<String, (u16, String)>,
+    seen: &Mutex<Vec<String>>,
+) -> Option<()> {
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let target = request_line.split_whitespace().nth(1)?.to_string();
+
+    // Drain the headers, requests carry no body
+    let mut line = String::new();
+    while reader.read_line(&mut line).ok()? > 2 {
+        line.clear();
+    }
+
+    seen.lock().unwrap().push(target.clone());
+
+    let (status, body) = routes
+        .get(&target)
+        .cloned()
+        .or_else(|| batch(&target, routes))
+        .unwrap_or((404, "Not Found".to_string()));
+    let content_type = if body.starts_with('{') {
+        "application/json"
+    } else {
+        "text/plain"
+    };
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).ok()
+}
@@ -0,0 +1,91 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+pub mod mock_api;
+
+use mantis_raydium_client::commands::{fetch_pool_info::PoolInfo, fetch_pool_keys::PoolKeys};
+use serde_json::json;
+
+pub const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+pub fn token(address: &str, program_id: &str) -> serde_json::Value {
+    json!({
+        "chainId": 101,
+        "address": address,
+        "programId": program_id,
+        "logoURI": "",
+        "symbol": "",
+        "name": "",
+        "decimals": 9,
+        "tags": [],
+        "extensions": {}
+    })
+}
+
+pub fn pool_keys(mint_b_program: &str) -> PoolKeys {
+    serde_json::from_value(json!({
+        "programId": "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+        "id": "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2",
+        "mintA": token("So11111111111111111111111111111111111111112", &spl_token::ID.to_string()),
+        "mintB": token("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", mint_b_program),
+        "lookupTableAccount": "",
+        "openTime": "0",
+        "vault": {
+            "A": "DQyrAcCrDXQ7NeoqGgDCZwBvWDcYmFCjSb9JtteuvPpz",
+            "B": "HLmqeL62xR1QoZ1HKKbXRrdN1p3phKpxRMb2VVopvBBz"
+        },
+        "authority": "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1",
+        "openOrders": "HmiHHzq4Fym9e1D4qzLS6LDDM3tNsCTBPDWHTLZ763jY",
+        "targetOrders": "CZza3Ej4Mc58MnxWA385itCC9jCo3L1D7zc3LKy1bZMR",
+        "mintLp": token("8HoQnePLqPj4M7PUDzfw8e3Ymdwgc7NLGnaTUapubyvu", &spl_token::ID.to_string()),
+        "marketProgramId": "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX",
+        "marketId": "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6",
+        "marketAuthority": "CTz5UMLQm2SRWHzQnU62Pi4yJqbNGjgRBHqqp6oDHfF7",
+        "marketBaseVault": "CKxTHwM9fPMRRvZmFnFoqKNd9pQR21c5Aq9bh5h9oghX",
+        "marketQuoteVault": "6A5NHCj1yF6urc9wZNe6Bcjj4LVszQNj5DwAWG97yzMu",
+        "marketBids": "5jWUncPNBMZJ3sTHKmMLszypVkoRK6bfEQMQUHweeQnh",
+        "marketAsks": "EaXdHx7x3mdGA38j5RSmKYSXMzAFzzUXCLNBEDXDn1d5",
+        "marketEventQueue": "8CvwxZ9Db6XbLD46NZwwmVDZZRDy7eydFcAGkXKh9axa"
+    }))
+    .expect("pool keys")
+}
+
+pub fn pool_info(id: &str, tvl: f64) -> PoolInfo {
+    let period = json!({
+        "volume": 0.0,
+        "volumeQuote": 0.0,
+        "volumeFee": 0.0,
+        "apr": 0.0,
+        "feeApr": 0.0,
+        "priceMin": 0.0,
+        "priceMax": 0.0,
+        "rewardApr": []
+    });
+    serde_json::from_value(json!({
+        "type": "Standard",
+        "programId": "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+        "id": id,
+        "mintA": token("So11111111111111111111111111111111111111112", &spl_token::ID.to_string()),
+        "mintB": token("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", &spl_token::ID.to_string()),
+        "price": 150.0,
+        "mintAmountA": 1000.0,
+        "mintAmountB": 150000.0,
+        "feeRate": 0.0025,
+        "openTime": "0",
+        "tvl": tvl,
+        "day": period,
+        "week": period,
+        "month": period,
+        "pooltype": [],
+        "rewardDefaultInfos": [],
+        "farmUpcomingCount": 0,
+        "farmOngoingCount": 0,
+        "farmFinishedCount": 0,
+        "marketId": "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6",
+        "lpMint": token("8HoQnePLqPj4M7PUDzfw8e3Ymdwgc7NLGnaTUapubyvu", &spl_token::ID.to_string()),
+        "lpPrice": 0.0,
+        "lpAmount": 0.0,
+        "burnPercent": 0.0
+    }))
+    .expect("pool info")
+}
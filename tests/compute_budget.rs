@@ -0,0 +1,65 @@
+use mantis_raydium_client::utils::compute_budget::{
+    compute_unit_limit, fee_percentile, transaction_fee, writable_accounts, PriorityFeeStrategy,
+    MAX_COMPUTE_UNIT_LIMIT,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+#[test]
+fn fee_percentiles() {
+    let mut fees: Vec<u64> = (1..=100).rev().collect();
+    assert_eq!(fee_percentile(&mut fees, PriorityFeeStrategy::P50), 50);
+    assert_eq!(fee_percentile(&mut fees, PriorityFeeStrategy::P75), 75);
+    assert_eq!(fee_percentile(&mut fees, PriorityFeeStrategy::P90), 90);
+}
+
+#[test]
+fn fee_percentile_of_few_samples() {
+    assert_eq!(fee_percentile(&mut [], PriorityFeeStrategy::P90), 0);
+    assert_eq!(fee_percentile(&mut [7], PriorityFeeStrategy::P50), 7);
+    assert_eq!(fee_percentile(&mut [0, 0, 10], PriorityFeeStrategy::P50), 0);
+    assert_eq!(
+        fee_percentile(&mut [0, 0, 10], PriorityFeeStrategy::P90),
+        10
+    );
+}
+
+#[test]
+fn writable_accounts_skip_signers_and_duplicates() {
+    let pool = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let program = Pubkey::new_unique();
+    let instruction = Instruction {
+        program_id: program,
+        accounts: vec![
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(owner, true),
+        ],
+        data: vec![],
+    };
+
+    assert_eq!(
+        writable_accounts(&[instruction.clone(), instruction]),
+        vec![pool, vault]
+    );
+}
+
+#[test]
+fn compute_unit_limit_adds_margin_up_to_the_maximum() {
+    assert_eq!(compute_unit_limit(100_000, 10), 110_000);
+    assert_eq!(compute_unit_limit(123_457, 0), 123_457);
+    assert_eq!(compute_unit_limit(1_300_000, 20), MAX_COMPUTE_UNIT_LIMIT);
+}
+
+#[test]
+fn transaction_fee_rounds_the_priority_fee_up() {
+    assert_eq!(transaction_fee(1, 0, 200_000), 5_000);
+    assert_eq!(transaction_fee(2, 1, 200_000), 10_001);
+    assert_eq!(transaction_fee(1, 1, 1_000_001), 5_002);
+    assert_eq!(transaction_fee(1, 10_000, 100_000), 6_000);
+}
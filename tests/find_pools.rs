@@ -0,0 +1,144 @@
+use mantis_raydium_client::{
+    commands::find_pools::{found_pool, mint_filters, rank_pools, FoundPool},
+    state::amm_info::AmmInfo,
+    utils::client::program_accounts_config,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+const WSOL: &str = "So11111111111111111111111111111111111111112";
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+fn pool(
+    pool_id: &str,
+    (base_mint, base_reserve): (&str, f64),
+    (quote_mint, quote_reserve): (&str, f64),
+    tvl: Option<f64>,
+    volume_24h: Option<f64>,
+) -> FoundPool {
+    FoundPool {
+        pool_id: pool_id.to_string(),
+        base_mint: base_mint.to_string(),
+        quote_mint: quote_mint.to_string(),
+        base_reserve,
+        quote_reserve,
+        tvl,
+        volume_24h,
+        fee_rate: 0.0025,
+    }
+}
+
+fn ids(pools: &[FoundPool]) -> Vec<&str> {
+    pools.iter().map(|pool| pool.pool_id.as_str()).collect()
+}
+
+#[test]
+fn mint_offsets_match_the_amm_layout() {
+    assert_eq!(AmmInfo::LEN, 752);
+    assert_eq!(AmmInfo::COIN_MINT_OFFSET, 400);
+    assert_eq!(AmmInfo::PC_MINT_OFFSET, 432);
+}
+
+#[test]
+fn program_accounts_are_requested_as_base64() {
+    // Base58 is refused for accounts over 128 bytes, AmmInfo is 752
+    let base = Pubkey::new_unique();
+    let quote = Pubkey::new_unique();
+    let config = program_accounts_config(mint_filters(&base, &quote));
+
+    assert_eq!(
+        config.account_config.encoding,
+        Some(UiAccountEncoding::Base64)
+    );
+    let filters = config.filters.expect("filters");
+    assert_eq!(filters.len(), 3);
+    assert_eq!(filters[0], RpcFilterType::DataSize(752));
+}
+
+#[test]
+fn ranks_by_tvl_then_volume() {
+    let mut pools = vec![
+        pool(
+            "small",
+            (WSOL, 1.0),
+            (USDC, 150.0),
+            Some(300.0),
+            Some(5000.0),
+        ),
+        pool("quiet", (USDC, 1.0), (WSOL, 1.0), Some(9000.0), Some(10.0)),
+        pool("busy", (WSOL, 1.0), (USDC, 1.0), Some(9000.0), Some(800.0)),
+    ];
+    rank_pools(&mut pools, USDC);
+    assert_eq!(ids(&pools), vec!["busy", "quiet", "small"]);
+}
+
+#[test]
+fn ranks_onchain_pools_by_reserve_of_either_orientation() {
+    // Without TVL, depth in the second mint decides whichever side it is on
+    let mut pools = vec![
+        pool("shallow", (WSOL, 900.0), (USDC, 10.0), None, None),
+        pool("reversed", (USDC, 500.0), (WSOL, 1.0), None, None),
+        pool("deep", (WSOL, 2.0), (USDC, 200.0), None, None),
+    ];
+    rank_pools(&mut pools, USDC);
+    assert_eq!(ids(&pools), vec!["reversed", "deep", "shallow"]);
+
+    rank_pools(&mut pools, WSOL);
+    assert_eq!(ids(&pools), vec!["shallow", "deep", "reversed"]);
+}
+
+fn vault(amount: u64) -> Option<Account> {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount {
+        amount,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    Some(Account {
+        data,
+        owner: spl_token::ID,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn onchain_pool_reserves_exclude_pending_pnl() {
+    let mut amm = AmmInfo {
+        coin_decimals: 9,
+        pc_decimals: 6,
+        ..Default::default()
+    };
+    amm.state_data.need_take_pnl_coin = 1_000_000_000;
+
+    let pool = found_pool(
+        &Pubkey::new_unique(),
+        &amm,
+        &[vault(3_000_000_000), vault(2_000_000)],
+    )
+    .unwrap();
+    assert_eq!(pool.base_reserve, 2.0);
+    assert_eq!(pool.quote_reserve, 2.0);
+}
+
+#[test]
+fn onchain_pools_that_cant_be_read_are_rejected() {
+    let pool_id = Pubkey::new_unique();
+    let mut amm = AmmInfo::default();
+
+    // Missing vault
+    assert!(found_pool(&pool_id, &amm, &[vault(1), None]).is_err());
+
+    // Vault that isn't a token account
+    let garbage = Some(Account {
+        data: vec![1, 2, 3],
+        ..Default::default()
+    });
+    assert!(found_pool(&pool_id, &amm, &[vault(1), garbage]).is_err());
+
+    // PnL above the vault balance
+    amm.state_data.need_take_pnl_pc = 2;
+    assert!(found_pool(&pool_id, &amm, &[vault(1), vault(1)]).is_err());
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::pool_keys;
+use mantis_raydium_client::{
+    commands::{fetch_pool_info::PoolInfo, fetch_pool_keys::PoolKeys},
+    error::Error,
+    pool_source::PoolSource,
+    utils::guardrails::{notional_usd, GuardrailsConfig, Trade},
+};
+
+const WSOL: &str = "So11111111111111111111111111111111111111112";
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+fn trade(pool_keys: &PoolKeys) -> Trade<'_> {
+    Trade {
+        pool_keys,
+        slippage_percentage: 1.0,
+        base_amount: 1_000_000_000,
+        quote_amount: 0,
+    }
+}
+
+#[test]
+fn default_guardrails_allow_anything() {
+    let pool_keys = pool_keys(&spl_token::ID.to_string());
+    let violations = GuardrailsConfig::default().violations(&trade(&pool_keys), Some(1e12));
+    assert!(violations.is_empty());
+}
+
+#[test]
+fn slippage_and_notional_limits() {
+    let pool_keys = pool_keys(&spl_token::ID.to_string());
+    let guardrails = GuardrailsConfig {
+        max_slippage_percentage: Some(0.5),
+        max_notional_usd: Some(100.0),
+        ..Default::default()
+    };
+
+    let violations = guardrails.violations(&trade(&pool_keys), Some(150.0));
+    assert_eq!(violations.len(), 2);
+
+    let mut within = trade(&pool_keys);
+    within.slippage_percentage = 0.5;
+    assert!(guardrails.violations(&within, Some(100.0)).is_empty());
+}
+
+#[test]
+fn pool_and_mint_allow_lists() {
+    let pool_keys = pool_keys(&spl_token::ID.to_string());
+
+    let guardrails = GuardrailsConfig {
+        allowed_pool_ids: vec![pool_keys.id.clone()],
+        allowed_mints: vec![WSOL.to_string(), USDC.to_string()],
+        ..Default::default()
+    };
+    assert!(guardrails.violations(&trade(&pool_keys), None).is_empty());
+
+    let guardrails = GuardrailsConfig {
+        allowed_pool_ids: vec!["AgFnRLUScRD2E4nWQxW73hdbSN7eKEUb2jHX7tx9YTYc".to_string()],
+        allowed_mints: vec![WSOL.to_string()],
+        ..Default::default()
+    };
+    let violations = guardrails.violations(&trade(&pool_keys), None);
+    assert_eq!(
+        violations,
+        vec![
+            format!("pool {} is not allowed", pool_keys.id),
+            format!("mint {} is not allowed", USDC),
+        ]
+    );
+}
+
+/// Source serving a single pool info, whatever the pool id.
+struct InfoSource {
+    mint_amount_a: f64,
+    tvl: f64,
+}
+
+impl PoolSource for InfoSource {
+    async fn pool_keys(&self, pool_id: &str) -> Result<PoolKeys, Error> {
+        Err(Error::PoolNotFound(pool_id.to_string()))
+    }
+
+    async fn pool_info(&self, pool_id: &str) -> Result<PoolInfo, Error> {
+        let mut info = common::pool_info(pool_id, self.tvl);
+        info.mintAmountA = self.mint_amount_a;
+        Ok(info)
+    }
+}
+
+#[tokio::test]
+async fn notional_prices_each_side_at_half_the_tvl() {
+    let pool_keys = pool_keys(&spl_token::ID.to_string());
+    let source = InfoSource {
+        mint_amount_a: 1000.0,
+        tvl: 300000.0,
+    };
+    let notional = notional_usd(&source, &trade(&pool_keys)).await.unwrap();
+    assert!((notional - 150.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn notional_fails_on_a_degenerate_pool() {
+    let pool_keys = pool_keys(&spl_token::ID.to_string());
+    for (mint_amount_a, tvl) in [(0.0, 300000.0), (-1.0, 300000.0), (1000.0, 0.0)] {
+        let source = InfoSource { mint_amount_a, tvl };
+        assert!(matches!(
+            notional_usd(&source, &trade(&pool_keys)).await,
+            Err(Error::Guardrail(_))
+        ));
+    }
+
+    // A side that isn't traded doesn't need a price
+    let source = InfoSource {
+        mint_amount_a: 1000.0,
+        tvl: 300000.0,
+    };
+    let mut quote_only = trade(&pool_keys);
+    quote_only.base_amount = 0;
+    quote_only.quote_amount = 1_000_000;
+    assert!(notional_usd(&source, &quote_only).await.is_ok());
+}
@@ -0,0 +1,165 @@
+//! Wire format of the AMM v4 deposit and withdraw instructions.
+//!
+//! These pin the data layout and account order the program unpacks. Running
+//! them against the program itself needs the AMM v4 and OpenBook binaries in
+//! an in-process SVM, which this suite doesn't load.
+
+mod common;
+
+use mantis_raydium_client::instructions::{
+    add_liquidity::{
+        make_add_liquidity_instruction, FixedSide, IxUserKeys as AddIxUserKeys,
+        LiquidityAddInstructionParams,
+    },
+    remove_liquidity::{
+        make_remove_liquidity_instruction, IxUserKeys as RemoveIxUserKeys,
+        RemoveLiquidityInstructionParams,
+    },
+};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+struct User {
+    base: Pubkey,
+    quote: Pubkey,
+    lp: Pubkey,
+    owner: Pubkey,
+}
+
+impl User {
+    fn new() -> Self {
+        Self {
+            base: Pubkey::new_unique(),
+            quote: Pubkey::new_unique(),
+            lp: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+        }
+    }
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn account_keys(ix: &Instruction) -> Vec<String> {
+    ix.accounts.iter().map(|a| a.pubkey.to_string()).collect()
+}
+
+fn add(fixed_side: FixedSide, user: &User) -> Instruction {
+    let pool_keys = common::pool_keys(&spl_token::ID.to_string());
+    make_add_liquidity_instruction(LiquidityAddInstructionParams {
+        rpc_pool_keys: &pool_keys,
+        user_keys: &AddIxUserKeys {
+            base_token_account: user.base,
+            quote_token_account: user.quote,
+            lp_token_account: user.lp,
+            owner: user.owner,
+        },
+        base_amount_in: 1_000,
+        quote_amount_in: 2_000,
+        fixed_side,
+        other_amount_min: 1_900,
+    })
+    .expect("add liquidity instruction")
+}
+
+#[test]
+fn add_liquidity_data_puts_fixed_side_before_other_amount_min() {
+    let user = User::new();
+    let ix = add(FixedSide::Base, &user);
+
+    assert_eq!(ix.data.len(), 33);
+    assert_eq!(ix.data[0], 3);
+    assert_eq!(u64_at(&ix.data, 1), 1_000);
+    assert_eq!(u64_at(&ix.data, 9), 2_000);
+    assert_eq!(u64_at(&ix.data, 17), 0);
+    assert_eq!(u64_at(&ix.data, 25), 1_900);
+
+    let ix = add(FixedSide::Quote, &user);
+    assert_eq!(u64_at(&ix.data, 17), 1);
+}
+
+#[test]
+fn add_liquidity_accounts_follow_program_order() {
+    let pool_keys = common::pool_keys(&spl_token::ID.to_string());
+    let user = User::new();
+    let ix = add(FixedSide::Base, &user);
+
+    assert_eq!(ix.program_id.to_string(), pool_keys.programId);
+    assert_eq!(
+        account_keys(&ix),
+        vec![
+            spl_token::ID.to_string(),
+            pool_keys.id.clone(),
+            pool_keys.authority.clone(),
+            pool_keys.openOrders.clone(),
+            pool_keys.targetOrders.clone(),
+            pool_keys.mintLp.address.clone(),
+            pool_keys.vault.A.clone(),
+            pool_keys.vault.B.clone(),
+            pool_keys.marketId.clone(),
+            user.base.to_string(),
+            user.quote.to_string(),
+            user.lp.to_string(),
+            user.owner.to_string(),
+            pool_keys.marketEventQueue.clone(),
+        ]
+    );
+
+    let signers = ix.accounts.iter().filter(|a| a.is_signer).count();
+    assert_eq!(signers, 1);
+    assert!(ix.accounts[12].is_signer);
+}
+
+#[test]
+fn remove_liquidity_data_and_accounts_follow_program_order() {
+    let pool_keys = common::pool_keys(&spl_token::ID.to_string());
+    let user = User::new();
+    let ix = make_remove_liquidity_instruction(RemoveLiquidityInstructionParams {
+        rpc_pool_keys: &pool_keys,
+        user_keys: &RemoveIxUserKeys {
+            base_token_account: user.base,
+            quote_token_account: user.quote,
+            lp_token_account: user.lp,
+            owner: user.owner,
+        },
+        lp_amount: 500,
+        base_amount_min: 10,
+        quote_amount_min: 20,
+    })
+    .expect("remove liquidity instruction");
+
+    assert_eq!(ix.data.len(), 25);
+    assert_eq!(ix.data[0], 4);
+    assert_eq!(u64_at(&ix.data, 1), 500);
+    assert_eq!(u64_at(&ix.data, 9), 10);
+    assert_eq!(u64_at(&ix.data, 17), 20);
+
+    assert_eq!(
+        account_keys(&ix),
+        vec![
+            spl_token::ID.to_string(),
+            pool_keys.id.clone(),
+            pool_keys.authority.clone(),
+            pool_keys.openOrders.clone(),
+            pool_keys.targetOrders.clone(),
+            pool_keys.mintLp.address.clone(),
+            pool_keys.vault.A.clone(),
+            pool_keys.vault.B.clone(),
+            pool_keys.id.clone(),
+            pool_keys.id.clone(),
+            pool_keys.marketProgramId.clone(),
+            pool_keys.marketId.clone(),
+            pool_keys.marketBaseVault.clone(),
+            pool_keys.marketQuoteVault.clone(),
+            pool_keys.marketAuthority.clone(),
+            user.lp.to_string(),
+            user.base.to_string(),
+            user.quote.to_string(),
+            user.owner.to_string(),
+            pool_keys.marketEventQueue.clone(),
+            pool_keys.marketBids.clone(),
+            pool_keys.marketAsks.clone(),
+        ]
+    );
+    assert!(ix.accounts[18].is_signer);
+}
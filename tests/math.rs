@@ -0,0 +1,402 @@
+use mantis_raydium_client::{
+    instructions::add_liquidity::FixedSide,
+    math::{
+        ceil_div, deposit, deposit_lp_min, max_amount_with_slippage, min_amount_with_slippage,
+        swap_base_in, swap_base_out, withdraw, zap_in, DepositQuote, PoolReserves, SwapDirection,
+        SwapFee, SwapQuote, WithdrawQuote,
+    },
+};
+use rust_decimal::Decimal;
+
+/// SOL/USDC-sized pool with PnL pending on both sides.
+fn large_pool() -> (PoolReserves, u64) {
+    let reserves = PoolReserves::new(1_234_567_890_123, 187_654_321_098, 1_000_000, 250_000)
+        .expect("reserves");
+    (reserves, 481_337_000_000)
+}
+
+#[test]
+fn reserves_exclude_pending_pnl() {
+    let (reserves, _) = large_pool();
+    assert_eq!(reserves.base, 1_234_566_890_123);
+    assert_eq!(reserves.quote, 187_654_071_098);
+}
+
+#[test]
+fn reserves_reject_pnl_above_balance() {
+    assert!(PoolReserves::new(10, 10, 11, 0).is_err());
+}
+
+#[test]
+fn deposit_fixed_base() {
+    let (reserves, lp_supply) = large_pool();
+    let quote = deposit(&reserves, lp_supply, FixedSide::Base, 1_000_000_000).unwrap();
+    assert_eq!(
+        quote,
+        DepositQuote {
+            base_amount: 1_000_000_000,
+            quote_amount: 151_999_923,
+            lp_amount: 389_883_289,
+        }
+    );
+}
+
+#[test]
+fn deposit_fixed_quote() {
+    let (reserves, lp_supply) = large_pool();
+    let quote = deposit(&reserves, lp_supply, FixedSide::Quote, 150_000_000).unwrap();
+    assert_eq!(
+        quote,
+        DepositQuote {
+            base_amount: 986_842_612,
+            quote_amount: 150_000_000,
+            lp_amount: 384_753_443,
+        }
+    );
+}
+
+#[test]
+fn deposit_rounds_dust_like_the_program() {
+    let reserves = PoolReserves::new(1_000, 3, 0, 0).unwrap();
+    let quote = deposit(&reserves, 50, FixedSide::Base, 100).unwrap();
+    assert_eq!(quote.quote_amount, 0);
+    assert_eq!(quote.lp_amount, 5);
+}
+
+#[test]
+fn deposit_too_small_for_lp() {
+    let reserves = PoolReserves::new(1_000, 3, 0, 0).unwrap();
+    assert!(deposit(&reserves, 50, FixedSide::Base, 10).is_err());
+}
+
+#[test]
+fn deposit_into_empty_pool() {
+    let reserves = PoolReserves::new(0, 0, 0, 0).unwrap();
+    assert!(deposit(&reserves, 0, FixedSide::Base, 10).is_err());
+}
+
+#[test]
+fn withdraw_pro_rata() {
+    let (reserves, lp_supply) = large_pool();
+    let quote = withdraw(&reserves, lp_supply, 389_883_289).unwrap();
+    assert_eq!(
+        quote,
+        WithdrawQuote {
+            lp_amount: 389_883_289,
+            base_amount: 999_999_999,
+            quote_amount: 151_999_921,
+        }
+    );
+}
+
+#[test]
+fn withdraw_entire_supply() {
+    let (reserves, lp_supply) = large_pool();
+    let quote = withdraw(&reserves, lp_supply, lp_supply).unwrap();
+    assert_eq!(quote.base_amount, reserves.base);
+    assert_eq!(quote.quote_amount, reserves.quote);
+}
+
+#[test]
+fn withdraw_after_deposit_returns_at_most_the_deposit() {
+    let (reserves, lp_supply) = large_pool();
+    let deposited = deposit(&reserves, lp_supply, FixedSide::Base, 1_000_000_000).unwrap();
+    let quote = withdraw(
+        &reserves.after_deposit(&deposited).unwrap(),
+        lp_supply + deposited.lp_amount,
+        deposited.lp_amount,
+    )
+    .unwrap();
+    assert!(quote.base_amount <= deposited.base_amount);
+    assert!(quote.quote_amount <= deposited.quote_amount);
+}
+
+#[test]
+fn deposit_lp_min_covers_a_sale_to_the_slippage_bound() {
+    let (reserves, lp_supply) = large_pool();
+    let quoted = deposit(&reserves, lp_supply, FixedSide::Base, 1_000_000_000).unwrap();
+    let quote_amount_min = min_amount_with_slippage(quoted.quote_amount, Decimal::ONE).unwrap();
+    let lp_amount_min = deposit_lp_min(
+        &reserves,
+        lp_supply,
+        SwapFee::DEFAULT,
+        FixedSide::Base,
+        1_000_000_000,
+        quote_amount_min,
+    )
+    .unwrap();
+    assert!(lp_amount_min < quoted.lp_amount);
+
+    // Every base sale the bound accepts mints at least the minimum
+    for amount_in in [1_000_000, 1_000_000_000, 5_000_000_000, 6_000_000_000] {
+        let swap = swap_base_in(
+            &reserves,
+            SwapFee::DEFAULT,
+            SwapDirection::BaseToQuote,
+            amount_in,
+        )
+        .unwrap();
+        let moved = reserves
+            .after_swap(SwapDirection::BaseToQuote, &swap)
+            .unwrap();
+        let landed = deposit(&moved, lp_supply, FixedSide::Base, 1_000_000_000).unwrap();
+        if landed.quote_amount >= quote_amount_min {
+            assert!(landed.lp_amount >= lp_amount_min);
+        }
+    }
+}
+
+#[test]
+fn deposit_lp_min_without_slippage_covers_rounding() {
+    // The quote side of 1_000 base is exactly 2_000 here, and stays 2_000 after
+    // a small sale of base because it is rounded up
+    let reserves = swap_pool();
+    let lp_supply = 2_000_000;
+    let quoted = deposit(&reserves, lp_supply, FixedSide::Base, 1_000).unwrap();
+    assert_eq!(quoted.lp_amount, 2_000);
+
+    let swap = swap_base_in(&reserves, SwapFee::DEFAULT, SwapDirection::BaseToQuote, 200).unwrap();
+    let moved = reserves
+        .after_swap(SwapDirection::BaseToQuote, &swap)
+        .unwrap();
+    let landed = deposit(&moved, lp_supply, FixedSide::Base, 1_000).unwrap();
+    assert_eq!(landed.quote_amount, quoted.quote_amount);
+    assert!(landed.lp_amount < quoted.lp_amount);
+
+    let lp_amount_min = deposit_lp_min(
+        &reserves,
+        lp_supply,
+        SwapFee::DEFAULT,
+        FixedSide::Base,
+        1_000,
+        quoted.quote_amount,
+    )
+    .unwrap();
+    assert!(lp_amount_min <= landed.lp_amount);
+
+    // The burn is covered by what the moved deposit mints
+    withdraw(
+        &moved.after_deposit(&landed).unwrap(),
+        lp_supply + landed.lp_amount,
+        lp_amount_min,
+    )
+    .unwrap();
+}
+
+#[test]
+fn withdraw_rejects_invalid_amounts() {
+    let (reserves, lp_supply) = large_pool();
+    assert!(withdraw(&reserves, lp_supply, lp_supply + 1).is_err());
+    assert!(withdraw(&reserves, lp_supply, 1).is_err());
+}
+
+#[test]
+fn ceil_div_matches_program() {
+    assert_eq!(ceil_div(10, 5).unwrap(), 2);
+    assert_eq!(ceil_div(11, 5).unwrap(), 3);
+    assert_eq!(ceil_div(3, 10).unwrap(), 0);
+    assert_eq!(ceil_div(5, 10).unwrap(), 1);
+    assert!(ceil_div(1, 0).is_err());
+}
+
+#[test]
+fn slippage_bounds() {
+    let one_percent = Decimal::ONE;
+    assert_eq!(
+        min_amount_with_slippage(1_000_001, one_percent).unwrap(),
+        990_000
+    );
+    assert_eq!(
+        max_amount_with_slippage(1_000_001, one_percent).unwrap(),
+        1_010_002
+    );
+    assert_eq!(min_amount_with_slippage(123, Decimal::ZERO).unwrap(), 123);
+    assert!(min_amount_with_slippage(1, Decimal::from(101)).is_err());
+    assert!(max_amount_with_slippage(1, Decimal::from(-1)).is_err());
+}
+
+fn swap_pool() -> PoolReserves {
+    PoolReserves::new(1_000_000, 2_000_000, 0, 0).expect("reserves")
+}
+
+#[test]
+fn swap_base_in_takes_fee_from_input() {
+    let quote = swap_base_in(
+        &swap_pool(),
+        SwapFee::DEFAULT,
+        SwapDirection::BaseToQuote,
+        10_000,
+    )
+    .unwrap();
+    assert_eq!(
+        quote,
+        SwapQuote {
+            amount_in: 10_000,
+            amount_out: 19_752,
+            fee: 25,
+        }
+    );
+}
+
+#[test]
+fn swap_base_in_rounds_dust_fee_like_program() {
+    // 100 * 25 / 10_000 is below one half, so the program charges no fee
+    let quote = swap_base_in(
+        &swap_pool(),
+        SwapFee::DEFAULT,
+        SwapDirection::QuoteToBase,
+        100,
+    )
+    .unwrap();
+    assert_eq!(quote.fee, 0);
+    assert_eq!(quote.amount_out, 49);
+}
+
+#[test]
+fn swap_base_out_inverts_swap_base_in() {
+    let quote = swap_base_out(
+        &swap_pool(),
+        SwapFee::DEFAULT,
+        SwapDirection::BaseToQuote,
+        19_752,
+    )
+    .unwrap();
+    assert_eq!(
+        quote,
+        SwapQuote {
+            amount_in: 10_000,
+            amount_out: 19_752,
+            fee: 25,
+        }
+    );
+}
+
+#[test]
+fn swap_base_out_rejects_draining_the_pool() {
+    let reserves = swap_pool();
+    assert!(swap_base_out(
+        &reserves,
+        SwapFee::DEFAULT,
+        SwapDirection::QuoteToBase,
+        1_000_000
+    )
+    .is_err());
+    assert!(swap_base_out(&reserves, SwapFee::DEFAULT, SwapDirection::QuoteToBase, 0).is_err());
+}
+
+#[test]
+fn zap_in_leaves_only_rounding_dust() {
+    let (reserves, lp_supply) = large_pool();
+    let amount = 5_000_000_000;
+    let quote = zap_in(
+        &reserves,
+        lp_supply,
+        SwapFee::DEFAULT,
+        SwapDirection::BaseToQuote,
+        amount,
+        Decimal::ZERO,
+    )
+    .unwrap();
+
+    // Roughly half is swapped, a bit more to make up for the fee
+    assert!(quote.swap.amount_in > amount / 2);
+    assert!(quote.swap.amount_in < amount / 2 + amount / 500);
+
+    let base_used = quote.swap.amount_in + quote.deposit.base_amount;
+    assert_eq!(base_used + quote.leftover_in, amount);
+    assert_eq!(
+        quote.deposit.quote_amount + quote.leftover_out,
+        quote.swap.amount_out
+    );
+    assert!(quote.leftover_in <= 10);
+    assert!(quote.leftover_out <= 10);
+}
+
+#[test]
+fn zap_in_deposits_only_the_guaranteed_output() {
+    let (reserves, lp_supply) = large_pool();
+    let quote = zap_in(
+        &reserves,
+        lp_supply,
+        SwapFee::DEFAULT,
+        SwapDirection::BaseToQuote,
+        5_000_000_000,
+        Decimal::ONE,
+    )
+    .unwrap();
+
+    assert_eq!(quote.fixed_side, FixedSide::Quote);
+    assert_eq!(
+        quote.minimum_amount_out,
+        min_amount_with_slippage(quote.swap.amount_out, Decimal::ONE).unwrap()
+    );
+    assert_eq!(quote.deposit.quote_amount, quote.minimum_amount_out);
+    assert_eq!(
+        quote.leftover_out,
+        quote.swap.amount_out - quote.minimum_amount_out
+    );
+    assert_eq!(
+        quote.swap.amount_in + quote.deposit.base_amount + quote.leftover_in,
+        5_000_000_000
+    );
+}
+
+#[test]
+fn zap_in_from_quote_side() {
+    let quote = zap_in(
+        &swap_pool(),
+        2_000_000,
+        SwapFee::DEFAULT,
+        SwapDirection::QuoteToBase,
+        100_000,
+        Decimal::ZERO,
+    )
+    .unwrap();
+    assert_eq!(
+        quote.swap.amount_in + quote.deposit.quote_amount + quote.leftover_in,
+        100_000
+    );
+    assert_eq!(
+        quote.deposit.base_amount + quote.leftover_out,
+        quote.swap.amount_out
+    );
+    assert!(quote.deposit.lp_amount > 0);
+}
+
+#[test]
+fn zap_in_on_a_deep_pool_leaves_only_rounding_dust() {
+    let reserves = PoolReserves::new(u64::MAX / 2, u64::MAX / 3, 0, 0).unwrap();
+    let quote = zap_in(
+        &reserves,
+        u64::MAX / 4,
+        SwapFee::DEFAULT,
+        SwapDirection::QuoteToBase,
+        123_456_789_012_345,
+        Decimal::ZERO,
+    )
+    .unwrap();
+    assert!(quote.leftover_in <= 10);
+    assert!(quote.leftover_out <= 10);
+}
+
+#[test]
+fn zap_in_rejects_dust() {
+    assert!(zap_in(
+        &swap_pool(),
+        2_000_000,
+        SwapFee::DEFAULT,
+        SwapDirection::BaseToQuote,
+        1,
+        Decimal::ZERO
+    )
+    .is_err());
+}
+
+#[test]
+fn withdraw_then_reserves_shrink() {
+    let (reserves, lp_supply) = large_pool();
+    let quote = withdraw(&reserves, lp_supply, lp_supply / 10).unwrap();
+    let after = reserves.after_withdraw(&quote).unwrap();
+    assert_eq!(after.base, reserves.base - quote.base_amount);
+    assert_eq!(after.quote, reserves.quote - quote.quote_amount);
+}
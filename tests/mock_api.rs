@@ -0,0 +1,317 @@
+mod common;
+
+use common::mock_api::{
+    fixture, MockApi, DRIFTED_ID, FAILING_ID, POOL_ID, REJECTED_ID, REVERSED_ID, SPARSE_ID,
+    UNKNOWN_ID, USDC, WSOL,
+};
+use mantis_raydium_client::{
+    error::Error,
+    pool_source::{ApiPoolSource, PoolSource},
+};
+use solana_sdk::signature::Keypair;
+use std::{
+    collections::HashMap,
+    process::{Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[tokio::test]
+async fn serves_recorded_pool_info_and_keys() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(api.url());
+
+    let info = source.pool_info(POOL_ID).await.unwrap();
+    assert_eq!(info.id, POOL_ID);
+    assert_eq!(info.mintB.symbol, "USDC");
+    assert_eq!(info.tvl, 12239024.31);
+
+    let keys = source.pool_keys(POOL_ID).await.unwrap();
+    let expected = common::pool_keys(&spl_token::ID.to_string());
+    assert!(keys.account_mismatches(&expected).is_empty());
+    assert_eq!(keys.lookup_tables().unwrap().len(), 1);
+
+    assert_eq!(
+        api.requests(),
+        vec![
+            format!("/pools/info/ids?ids={}", POOL_ID),
+            format!("/pools/key/ids?ids={}", POOL_ID),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn trailing_slash_in_base_url_is_ignored() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(&format!("{}/", api.url()));
+
+    source.pool_info(POOL_ID).await.unwrap();
+    assert_eq!(
+        api.requests(),
+        vec![format!("/pools/info/ids?ids={}", POOL_ID)]
+    );
+}
+
+#[tokio::test]
+async fn batches_are_keyed_by_id_and_chunked() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(api.url());
+
+    let pool_ids = vec![
+        SPARSE_ID.to_string(),
+        UNKNOWN_ID.to_string(),
+        POOL_ID.to_string(),
+    ];
+    let pools = source.pool_keys_by_ids(&pool_ids).await.unwrap();
+    assert_eq!(pools.len(), 3);
+    assert_eq!(pools[POOL_ID].as_ref().unwrap().id, POOL_ID);
+    assert_eq!(pools[SPARSE_ID].as_ref().unwrap().id, SPARSE_ID);
+    assert!(pools[UNKNOWN_ID].is_none());
+    assert_eq!(api.requests().len(), 1);
+
+    let many = (0..150)
+        .map(|i| format!("Pool{:039}", i))
+        .collect::<Vec<_>>();
+    let pools = source.pool_info_by_ids(&many).await.unwrap();
+    assert_eq!(pools.len(), 150);
+    assert!(pools.values().all(Option::is_none));
+    assert_eq!(api.requests().len(), 3);
+}
+
+/// Body of a by-id response carrying `entries`, in that order.
+fn by_ids_body(entries: &[serde_json::Value]) -> String {
+    serde_json::json!({ "id": "batch", "success": true, "data": entries }).to_string()
+}
+
+/// First entry of the recorded response `name`.
+fn recorded_entry(name: &str) -> serde_json::Value {
+    let response: serde_json::Value = serde_json::from_str(&fixture(name)).unwrap();
+    response["data"][0].clone()
+}
+
+#[tokio::test]
+async fn batches_are_matched_by_the_returned_id() {
+    let pool = recorded_entry("pools_info_ids.json");
+    let sparse = recorded_entry("pools_info_ids_sparse.json");
+    let api = MockApi::start(HashMap::from([(
+        format!(
+            "/pools/info/ids?ids={},{},{}",
+            POOL_ID, UNKNOWN_ID, SPARSE_ID
+        ),
+        (200, by_ids_body(&[sparse, pool])),
+    )]));
+    let source = ApiPoolSource::new(api.url());
+
+    let pool_ids = [POOL_ID, UNKNOWN_ID, SPARSE_ID].map(str::to_string);
+    let pools = source.pool_info_by_ids(&pool_ids).await.unwrap();
+    assert_eq!(pools[POOL_ID].as_ref().unwrap().id, POOL_ID);
+    assert_eq!(pools[SPARSE_ID].as_ref().unwrap().id, SPARSE_ID);
+    assert!(pools[UNKNOWN_ID].is_none());
+}
+
+#[tokio::test]
+async fn pools_that_were_not_requested_are_refused() {
+    let api = MockApi::start(HashMap::from([(
+        format!("/pools/info/ids?ids={}", UNKNOWN_ID),
+        (200, by_ids_body(&[recorded_entry("pools_info_ids.json")])),
+    )]));
+    let source = ApiPoolSource::new(api.url());
+
+    match source.pool_info(UNKNOWN_ID).await {
+        Err(Error::Api(msg)) => assert!(msg.contains(POOL_ID), "{}", msg),
+        other => panic!("expected an API error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn http_errors_are_api_errors() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(api.url());
+
+    match source.pool_info(FAILING_ID).await {
+        Err(Error::Api(msg)) => assert!(msg.contains("500"), "{}", msg),
+        other => panic!("expected an API error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rejected_requests_are_typed() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(api.url());
+
+    match source.pool_info(REJECTED_ID).await {
+        Err(Error::ApiRejected { msg }) => assert_eq!(msg, "ids error"),
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+    assert!(matches!(
+        source.pool_keys(REJECTED_ID).await,
+        Err(Error::ApiRejected { .. })
+    ));
+}
+
+#[tokio::test]
+async fn unknown_pools_are_not_found() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(api.url());
+
+    match source.pool_info(UNKNOWN_ID).await {
+        Err(Error::PoolNotFound(pool_id)) => assert_eq!(pool_id, UNKNOWN_ID),
+        other => panic!("expected pool not found, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn schema_drift_names_the_field() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(api.url());
+
+    match source.pool_info(DRIFTED_ID).await {
+        Err(Error::SchemaMismatch { field }) => assert_eq!(field, "data[0].tvl"),
+        other => panic!("expected a schema mismatch, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn omitted_optional_fields_default() {
+    let api = MockApi::recorded();
+    let source = ApiPoolSource::new(api.url());
+
+    let info = source.pool_info(SPARSE_ID).await.unwrap();
+    assert_eq!(info.burnPercent, 0.0);
+    assert!(info.rewardDefaultInfos.is_empty());
+    assert!(info.marketId.is_empty());
+    assert_eq!(info.week.volume, 0.0);
+    assert_eq!(info.day.volume, 10344512.2);
+    assert!(info.mintA.tags.is_empty());
+
+    let keys = source.pool_keys(SPARSE_ID).await.unwrap();
+    assert!(keys.lookup_tables().unwrap().is_empty());
+}
+
+/// Run the CLI against `api` with an API-only pool source.
+fn run_cli(api: &MockApi, args: &[&str]) -> Output {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "mock-api-cli-{}-{}",
+        std::process::id(),
+        RUNS.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let keypair_path = dir.join("keypair.json");
+    std::fs::write(
+        &keypair_path,
+        serde_json::to_string(&Keypair::new().to_bytes().to_vec()).unwrap(),
+    )
+    .unwrap();
+    let config_path = dir.join("config.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "rpc_endpoint = \"http://127.0.0.1:1\"\napi_key = \"\"\nkeypair_path = {:?}\n\n[pool_source]\nkind = \"api\"\n",
+            keypair_path
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mantis-raydium-client"))
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--api-base-url", api.url(), "--output", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_dir_all(&dir);
+    output
+}
+
+#[test]
+fn cli_reads_pool_info_from_the_configured_api() {
+    let api = MockApi::recorded();
+    let output = run_cli(&api, &["fetch-pool-info", "--pool-id", POOL_ID]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let pools: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let expected: serde_json::Value =
+        serde_json::from_str(&fixture("pools_info_ids.json")).unwrap();
+    assert_eq!(pools[POOL_ID], expected["data"][0]);
+}
+
+#[test]
+fn cli_batches_repeated_and_listed_pool_ids() {
+    let api = MockApi::recorded();
+    let list = std::env::temp_dir().join(format!("mock-api-ids-{}.txt", std::process::id()));
+    std::fs::write(
+        &list,
+        format!("# pools to watch\n{}\n\n{}  # again\n", SPARSE_ID, POOL_ID),
+    )
+    .unwrap();
+
+    let output = run_cli(
+        &api,
+        &[
+            "fetch-pool-info",
+            "--pool-id",
+            POOL_ID,
+            "--pool-ids-file",
+            list.to_str().unwrap(),
+        ],
+    );
+    let _ = std::fs::remove_file(&list);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let pools: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let ids = pools.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(ids, vec![POOL_ID, SPARSE_ID]);
+    assert_eq!(
+        api.requests(),
+        vec![format!("/pools/info/ids?ids={},{}", POOL_ID, SPARSE_ID)]
+    );
+}
+
+#[test]
+fn cli_exit_codes_tell_api_failures_apart() {
+    let api = MockApi::recorded();
+    for (pool_id, code) in [
+        (UNKNOWN_ID, 3),
+        (REJECTED_ID, 4),
+        (DRIFTED_ID, 5),
+        (FAILING_ID, 6),
+    ] {
+        let output = run_cli(&api, &["fetch-pool-info", "--pool-id", pool_id]);
+        assert_eq!(output.status.code(), Some(code), "{}", pool_id);
+        assert!(output.stdout.is_empty());
+    }
+}
+
+#[test]
+fn cli_finds_amm_v4_pools_ranked_by_tvl() {
+    let api = MockApi::recorded();
+    let output = run_cli(&api, &["find-pools", "--mint-a", USDC, "--mint-b", WSOL]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The CPMM pool is left out despite its larger TVL
+    let pools: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let ids = pools
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|pool| pool["pool_id"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![POOL_ID, REVERSED_ID]);
+    assert_eq!(pools[0]["base_mint"], WSOL);
+    assert_eq!(pools[1]["base_mint"], USDC);
+    assert_eq!(pools[0]["volume_24h"], 10344512.2);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{pool_keys, TOKEN_2022_PROGRAM};
+use mantis_raydium_client::{commands::fetch_pool_keys::PoolKeys, error::Error};
+
+#[test]
+fn spl_token_pool_uses_spl_token_program() {
+    let pool_keys = pool_keys(&spl_token::ID.to_string());
+    assert_eq!(pool_keys.token_program().unwrap(), spl_token::ID);
+}
+
+#[test]
+fn empty_program_id_is_refused() {
+    let pool_keys = pool_keys("");
+    assert!(matches!(
+        pool_keys.mintB.program_id(),
+        Err(Error::SchemaMismatch { .. })
+    ));
+    assert!(pool_keys.token_program().is_err());
+}
+
+#[test]
+fn missing_program_id_fails_to_parse() {
+    let mut keys = serde_json::to_value(pool_keys(&spl_token::ID.to_string())).unwrap();
+    keys["mintB"].as_object_mut().unwrap().remove("programId");
+    let err = serde_json::from_value::<PoolKeys>(keys).unwrap_err();
+    assert!(err.to_string().contains("programId"));
+}
+
+#[test]
+fn token_2022_mint_is_refused() {
+    let pool_keys = pool_keys(TOKEN_2022_PROGRAM);
+    assert!(matches!(
+        pool_keys.token_program(),
+        Err(Error::UnsupportedToken(_))
+    ));
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use mantis_raydium_client::{
+    commands::{
+        fetch_pool_info::{self, PoolInfo},
+        fetch_pool_keys::PoolKeys,
+    },
+    config::Config,
+    error::Error,
+    pool_source::{all_found, CachePoolSource, PoolSource, PoolSourceKind},
+    RaydiumV4Client,
+};
+use solana_sdk::signature::Keypair;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+const POOL_ID: &str = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2";
+/// Valid pool id no source knows.
+const UNKNOWN_ID: &str = "AgFnRLUScRD2E4nWQxW73hdbSN7eKEUb2jHX7tx9YTYc";
+const LOOKUP_TABLE: &str = "AcL1Vo8oy1ULiavEcjSUcwfBSForXMudcZvDZy5nzJkU";
+
+/// Source serving the shared fixtures, counting how often it is asked.
+#[derive(Default)]
+struct FixtureSource {
+    calls: AtomicUsize,
+    /// Serve keys without their lookup table
+    no_lookup_table: bool,
+}
+
+impl FixtureSource {
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl PoolSource for FixtureSource {
+    async fn pool_keys(&self, pool_id: &str) -> Result<PoolKeys, Error> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if pool_id != POOL_ID {
+            return Err(Error::PoolNotFound(pool_id.to_string()));
+        }
+        let mut keys = common::pool_keys(&spl_token::ID.to_string());
+        if !self.no_lookup_table {
+            keys.lookupTableAccount = LOOKUP_TABLE.to_string();
+        }
+        Ok(keys)
+    }
+
+    async fn pool_info(&self, pool_id: &str) -> Result<PoolInfo, Error> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if pool_id != POOL_ID {
+            return Err(Error::PoolNotFound(pool_id.to_string()));
+        }
+        Ok(common::pool_info(pool_id, 300000.0))
+    }
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pool-source-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn cache_serves_pool_keys_from_disk() {
+    let dir = cache_dir("keys");
+    let cache = CachePoolSource::new(&dir, Duration::from_secs(60), FixtureSource::default());
+
+    let first = cache.pool_keys(POOL_ID).await.unwrap();
+    let second = cache.pool_keys(POOL_ID).await.unwrap();
+    assert!(first.account_mismatches(&second).is_empty());
+    assert_eq!(cache.inner().calls(), 1);
+
+    // A fresh cache over the same directory never reaches its source
+    let reopened = CachePoolSource::new(&dir, Duration::ZERO, FixtureSource::default());
+    let third = reopened.pool_keys(POOL_ID).await.unwrap();
+    assert!(first.account_mismatches(&third).is_empty());
+    assert_eq!(reopened.inner().calls(), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn cache_refetches_expired_pool_info() {
+    let dir = cache_dir("info");
+    let fresh = CachePoolSource::new(&dir, Duration::from_secs(60), FixtureSource::default());
+    assert_eq!(fresh.pool_info(POOL_ID).await.unwrap().tvl, 300000.0);
+    fresh.pool_info(POOL_ID).await.unwrap();
+    assert_eq!(fresh.inner().calls(), 1);
+
+    std::thread::sleep(Duration::from_millis(20));
+    let expired = CachePoolSource::new(&dir, Duration::from_millis(1), FixtureSource::default());
+    expired.pool_info(POOL_ID).await.unwrap();
+    assert_eq!(expired.inner().calls(), 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn cache_ignores_unreadable_entries_and_keeps_errors_uncached() {
+    let dir = cache_dir("corrupt");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("{}.keys.json", POOL_ID)), "not json").unwrap();
+
+    let cache = CachePoolSource::new(&dir, Duration::from_secs(60), FixtureSource::default());
+    assert_eq!(cache.pool_keys(POOL_ID).await.unwrap().id, POOL_ID);
+
+    assert!(cache.pool_keys(UNKNOWN_ID).await.is_err());
+    assert!(cache.pool_keys(UNKNOWN_ID).await.is_err());
+    assert_eq!(cache.inner().calls(), 3);
+    assert!(!dir.join(format!("{}.keys.json", UNKNOWN_ID)).exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn cache_rejects_pool_ids_that_arent_pubkeys() {
+    let dir = cache_dir("invalid");
+    let cache = CachePoolSource::new(&dir, Duration::from_secs(60), FixtureSource::default());
+    for pool_id in ["../escape", "unknown"] {
+        assert!(matches!(
+            cache.pool_keys(pool_id).await,
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            cache.pool_info(pool_id).await,
+            Err(Error::InvalidInput(_))
+        ));
+    }
+    assert_eq!(cache.inner().calls(), 0);
+    assert!(!dir.exists());
+}
+
+#[tokio::test]
+async fn cache_keeps_no_keys_missing_their_lookup_table() {
+    let dir = cache_dir("incomplete");
+    let source = FixtureSource {
+        no_lookup_table: true,
+        ..Default::default()
+    };
+    let cache = CachePoolSource::new(&dir, Duration::from_secs(60), source);
+
+    assert!(cache
+        .pool_keys(POOL_ID)
+        .await
+        .unwrap()
+        .lookup_tables()
+        .unwrap()
+        .is_empty());
+    cache.pool_keys(POOL_ID).await.unwrap();
+    assert_eq!(cache.inner().calls(), 2);
+    assert!(!dir.join(format!("{}.keys.json", POOL_ID)).exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn cache_only_asks_for_missing_pools() {
+    let dir = cache_dir("batch");
+    let cache = CachePoolSource::new(&dir, Duration::from_secs(60), FixtureSource::default());
+    cache.pool_keys(POOL_ID).await.unwrap();
+
+    let pool_ids = vec![POOL_ID.to_string(), UNKNOWN_ID.to_string()];
+    let pools = cache.pool_keys_by_ids(&pool_ids).await.unwrap();
+    assert_eq!(pools[POOL_ID].as_ref().unwrap().id, POOL_ID);
+    assert!(pools[UNKNOWN_ID].is_none());
+    assert_eq!(cache.inner().calls(), 2);
+
+    assert!(matches!(
+        all_found(pools),
+        Err(Error::PoolNotFound(pool_ids)) if pool_ids == UNKNOWN_ID
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn raydium_client_reads_pools_from_an_injected_source() {
+    let dir = cache_dir("client");
+    std::fs::create_dir_all(&dir).unwrap();
+    let keypair_path = dir.join("keypair.json");
+    std::fs::write(
+        &keypair_path,
+        serde_json::to_string(&Keypair::new().to_bytes().to_vec()).unwrap(),
+    )
+    .unwrap();
+    let config = Config {
+        keypair_path,
+        ..Config::default()
+    };
+
+    let raydium = RaydiumV4Client::new(config)
+        .unwrap()
+        .with_pool_source(FixtureSource::default());
+    assert_eq!(raydium.pool_keys(POOL_ID).await.unwrap().id, POOL_ID);
+    let pools = fetch_pool_info::execute(&raydium, &[POOL_ID.to_string()])
+        .await
+        .unwrap();
+    assert_eq!(pools[POOL_ID].tvl, 300000.0);
+    assert_eq!(raydium.pool_source().calls(), 2);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn pool_source_config_defaults_to_rpc_and_reads_overrides() {
+    let config = Config::default();
+    assert_eq!(config.pool_source.kind, PoolSourceKind::Rpc);
+    assert_eq!(config.pool_source.api_base_url, "https://api-v3.raydium.io");
+
+    let config: Config = toml::from_str(
+        r#"
+        rpc_endpoint = "http://localhost:8899"
+        api_key = ""
+        keypair_path = "keypair.json"
+
+        [pool_source]
+        kind = "cache"
+        api_base_url = "http://localhost:3000/"
+        cache_ttl_secs = 5
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.pool_source.kind, PoolSourceKind::Cache);
+    assert_eq!(config.pool_source.api_base_url, "http://localhost:3000/");
+    assert_eq!(config.pool_source.cache_ttl_secs, 5);
+}
@@ -0,0 +1,62 @@
+use mantis_raydium_client::utils::printer::{format_output, OutputFormat};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Amount {
+    token: &'static str,
+    amount: u64,
+}
+
+#[derive(Serialize)]
+struct Result {
+    signature: Option<&'static str>,
+    amounts: Vec<Amount>,
+}
+
+fn result() -> Result {
+    Result {
+        signature: None,
+        amounts: vec![
+            Amount {
+                token: "SOL",
+                amount: 1,
+            },
+            Amount {
+                token: "USDC",
+                amount: 250,
+            },
+        ],
+    }
+}
+
+#[test]
+fn json_output_round_trips() {
+    let output = format_output(&result(), OutputFormat::Json).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(value["amounts"][1]["token"], "USDC");
+    assert!(value["signature"].is_null());
+}
+
+#[test]
+fn text_output_flattens_nested_fields() {
+    let output = format_output(&result(), OutputFormat::Text).unwrap();
+    assert_eq!(
+        output,
+        "signature: \namounts.0.token: SOL\namounts.0.amount: 1\namounts.1.token: USDC\namounts.1.amount: 250"
+    );
+}
+
+#[test]
+fn table_output_has_a_row_per_item() {
+    let output = format_output(&result().amounts, OutputFormat::Table).unwrap();
+    assert_eq!(output, "token  amount\n-----  ------\nSOL    1\nUSDC   250");
+}
+
+#[test]
+fn table_output_of_a_single_item_lists_fields() {
+    let output = format_output(&result().amounts[0], OutputFormat::Table).unwrap();
+    assert_eq!(
+        output,
+        "field   value\n------  -----\ntoken   SOL\namount  1"
+    );
+}
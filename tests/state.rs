@@ -0,0 +1,207 @@
+mod common;
+
+use common::pool_keys;
+use mantis_raydium_client::{
+    commands::fetch_pool_keys::PoolKeys,
+    state::{
+        amm_info::{AmmInfo, AMM_V4_PROGRAM_ID},
+        market::{market_keys_from_account, MarketState},
+        open_orders::OpenOrders,
+    },
+};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Vault signer nonce of the SOL/USDC market on mainnet.
+const MARKET_VAULT_SIGNER_NONCE: u64 = 1;
+/// AMM authority nonce shared by every AMM v4 pool on mainnet.
+const AMM_AUTHORITY_NONCE: u64 = 254;
+
+fn pubkey(address: &str) -> Pubkey {
+    Pubkey::from_str(address).expect("pubkey")
+}
+
+/// Account bytes written field by field at the offsets the programs use.
+struct Bytes(Vec<u8>);
+
+impl Bytes {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len])
+    }
+
+    fn u64(mut self, offset: usize, value: u64) -> Self {
+        self.0[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn pubkey(mut self, offset: usize, address: &str) -> Self {
+        self.0[offset..offset + 32].copy_from_slice(pubkey(address).as_ref());
+        self
+    }
+
+    fn text(mut self, offset: usize, text: &str) -> Self {
+        self.0[offset..offset + text.len()].copy_from_slice(text.as_bytes());
+        self
+    }
+}
+
+/// SOL/USDC AMM account as laid out by the AMM v4 program.
+fn amm_account(keys: &PoolKeys) -> Vec<u8> {
+    Bytes::new(752)
+        .u64(0, 6)
+        .u64(8, AMM_AUTHORITY_NONCE)
+        .u64(32, 9)
+        .u64(40, 6)
+        .u64(176, 25)
+        .u64(184, 10_000)
+        .u64(192, 1_000_000)
+        .u64(200, 250_000)
+        .pubkey(336, &keys.vault.A)
+        .pubkey(368, &keys.vault.B)
+        .pubkey(400, &keys.mintA.address)
+        .pubkey(432, &keys.mintB.address)
+        .pubkey(464, &keys.mintLp.address)
+        .pubkey(496, &keys.openOrders)
+        .pubkey(528, &keys.marketId)
+        .pubkey(560, &keys.marketProgramId)
+        .pubkey(592, &keys.targetOrders)
+        .u64(720, 481_337_000_000)
+        .0
+}
+
+/// SOL/USDC market account as laid out by the Serum/OpenBook program.
+fn market_account(keys: &PoolKeys, own_address: &str) -> Vec<u8> {
+    Bytes::new(388)
+        .text(0, "serum")
+        .u64(5, 0b11)
+        .pubkey(13, own_address)
+        .u64(45, MARKET_VAULT_SIGNER_NONCE)
+        .pubkey(53, &keys.mintA.address)
+        .pubkey(85, &keys.mintB.address)
+        .pubkey(117, &keys.marketBaseVault)
+        .pubkey(165, &keys.marketQuoteVault)
+        .pubkey(253, &keys.marketEventQueue)
+        .pubkey(285, &keys.marketBids)
+        .pubkey(317, &keys.marketAsks)
+        .u64(349, 1_000_000)
+        .u64(357, 1)
+        .text(381, "padding")
+        .0
+}
+
+#[test]
+fn amm_info_decodes_the_program_layout() {
+    let keys = pool_keys(&spl_token::ID.to_string());
+    let amm = AmmInfo::from_account_data(&amm_account(&keys)).unwrap();
+
+    assert_eq!(amm.status, 6);
+    assert_eq!((amm.coin_decimals, amm.pc_decimals), (9, 6));
+    assert_eq!(amm.fees.swap_fee_numerator, 25);
+    assert_eq!(amm.fees.swap_fee_denominator, 10_000);
+    assert_eq!(amm.state_data.need_take_pnl_coin, 1_000_000);
+    assert_eq!(amm.state_data.need_take_pnl_pc, 250_000);
+    assert_eq!(amm.coin_vault, pubkey(&keys.vault.A));
+    assert_eq!(amm.pc_vault, pubkey(&keys.vault.B));
+    assert_eq!(amm.coin_vault_mint, pubkey(&keys.mintA.address));
+    assert_eq!(amm.pc_vault_mint, pubkey(&keys.mintB.address));
+    assert_eq!(amm.lp_mint, pubkey(&keys.mintLp.address));
+    assert_eq!(amm.open_orders, pubkey(&keys.openOrders));
+    assert_eq!(amm.market, pubkey(&keys.marketId));
+    assert_eq!(amm.market_program, pubkey(&keys.marketProgramId));
+    assert_eq!(amm.target_orders, pubkey(&keys.targetOrders));
+    assert_eq!(amm.lp_amount, 481_337_000_000);
+}
+
+#[test]
+fn amm_authority_matches_mainnet() {
+    let keys = pool_keys(&spl_token::ID.to_string());
+    let amm = AmmInfo::from_account_data(&amm_account(&keys)).unwrap();
+    assert_eq!(
+        amm.authority(&AMM_V4_PROGRAM_ID).unwrap(),
+        pubkey(&keys.authority)
+    );
+}
+
+#[test]
+fn amm_info_rejects_the_wrong_size() {
+    assert!(AmmInfo::from_account_data(&[0; 751]).is_err());
+    assert!(AmmInfo::from_account_data(&[0; 753]).is_err());
+}
+
+#[test]
+fn market_keys_decode_the_program_layout() {
+    let keys = pool_keys(&spl_token::ID.to_string());
+    let market_id = pubkey(&keys.marketId);
+    let market_program = pubkey(&keys.marketProgramId);
+
+    let market = MarketState::from_account_data(&market_account(&keys, &keys.marketId)).unwrap();
+    assert_eq!(market.own_address, market_id);
+    assert_eq!(market.coin_mint, pubkey(&keys.mintA.address));
+    assert_eq!(market.pc_mint, pubkey(&keys.mintB.address));
+    assert_eq!(market.coin_lot_size, 1_000_000);
+    assert_eq!(market.pc_lot_size, 1);
+
+    let market_keys = market_keys_from_account(
+        &market_id,
+        &market_account(&keys, &keys.marketId),
+        &market_program,
+    )
+    .unwrap();
+    assert_eq!(market_keys.id, market_id);
+    assert_eq!(market_keys.program_id, market_program);
+    assert_eq!(market_keys.base_vault, pubkey(&keys.marketBaseVault));
+    assert_eq!(market_keys.quote_vault, pubkey(&keys.marketQuoteVault));
+    assert_eq!(market_keys.bids, pubkey(&keys.marketBids));
+    assert_eq!(market_keys.asks, pubkey(&keys.marketAsks));
+    assert_eq!(market_keys.event_queue, pubkey(&keys.marketEventQueue));
+}
+
+#[test]
+fn market_vault_signer_matches_mainnet() {
+    let keys = pool_keys(&spl_token::ID.to_string());
+    let market = MarketState::from_account_data(&market_account(&keys, &keys.marketId)).unwrap();
+    assert_eq!(
+        market.vault_signer(&pubkey(&keys.marketProgramId)).unwrap(),
+        pubkey(&keys.marketAuthority)
+    );
+}
+
+#[test]
+fn market_keys_refuse_another_market() {
+    let keys = pool_keys(&spl_token::ID.to_string());
+    let data = market_account(&keys, &keys.id);
+    assert!(market_keys_from_account(
+        &pubkey(&keys.marketId),
+        &data,
+        &pubkey(&keys.marketProgramId)
+    )
+    .is_err());
+}
+
+#[test]
+fn market_state_rejects_short_accounts() {
+    assert!(MarketState::from_account_data(&[0; 387]).is_err());
+}
+
+#[test]
+fn open_orders_decode_the_program_layout() {
+    let keys = pool_keys(&spl_token::ID.to_string());
+    let data = Bytes::new(3228)
+        .text(0, "serum")
+        .pubkey(13, &keys.marketId)
+        .pubkey(45, &keys.authority)
+        .u64(77, 1)
+        .u64(85, 2_000_000_000)
+        .u64(93, 3)
+        .u64(101, 300_000_000)
+        .text(3221, "padding")
+        .0;
+
+    let open_orders = OpenOrders::from_account_data(&data).unwrap();
+    assert_eq!(open_orders.market, pubkey(&keys.marketId));
+    assert_eq!(open_orders.owner, pubkey(&keys.authority));
+    assert_eq!(open_orders.native_coin_free, 1);
+    assert_eq!(open_orders.native_coin_total, 2_000_000_000);
+    assert_eq!(open_orders.native_pc_free, 3);
+    assert_eq!(open_orders.native_pc_total, 300_000_000);
+}